@@ -37,10 +37,7 @@ fn main() {
                 .unwrap()
                 .to_string_lossy()
                 .to_string(),
-            Action::ToWindows => {
-                let path = std::fs::canonicalize(path).unwrap();
-                config.to_wine_path(path).unwrap().to_string()
-            }
+            Action::ToWindows => config.to_wine_path_resolved(path).unwrap().to_string(),
         }
     )
 }
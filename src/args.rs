@@ -0,0 +1,73 @@
+//! Conversion of command line arguments for Wine processes.
+
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+use crate::WineConfig;
+
+impl WineConfig {
+    /// Convert the arguments of a command that are absolute native paths of existing files to
+    /// wine paths, to pass them to a Wine process.
+    ///
+    /// Other arguments, including relative paths, are kept as is. So are paths that can't be
+    /// converted. Use [`WineConfig::to_wine_args_with`] to choose which arguments are converted.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::process::Command;
+    /// let config = WineConfig::from_env().unwrap();
+    /// // mytool game.exe /home/username/save.dat
+    /// let args = config.to_wine_args(std::env::args_os().skip(1));
+    /// // wine game.exe z:\home\username\save.dat
+    /// Command::new("wine").args(args).status().unwrap();
+    /// ```
+    pub fn to_wine_args(
+        &self,
+        args: impl IntoIterator<Item = impl Into<OsString>>,
+    ) -> Vec<OsString> {
+        self.to_wine_args_with(args, |arg| {
+            let path = Path::new(arg);
+            path.is_absolute() && path.exists()
+        })
+    }
+
+    /// Convert the arguments of a command for which `should_convert` returns `true` to wine
+    /// paths, to pass them to a Wine process.
+    ///
+    /// Other arguments are kept as is. So are arguments that can't be converted.
+    ///
+    /// ```rust
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    ///     ('z', PathBuf::from("/")),
+    /// ]);
+    /// let args = config.to_wine_args_with(
+    ///     vec!["game.exe", "-save", "/home/username/save.dat", "/fullscreen"],
+    ///     |arg| arg.to_str().is_some_and(|arg| arg.starts_with("/home/")),
+    /// );
+    /// assert_eq!(args, ["game.exe", "-save", r"z:\home\username\save.dat", "/fullscreen"]);
+    /// ```
+    pub fn to_wine_args_with(
+        &self,
+        args: impl IntoIterator<Item = impl Into<OsString>>,
+        mut should_convert: impl FnMut(&OsStr) -> bool,
+    ) -> Vec<OsString> {
+        args.into_iter()
+            .map(|arg| {
+                let arg = arg.into();
+                if !should_convert(&arg) {
+                    return arg;
+                }
+                match self.to_wine_path(Path::new(&arg)) {
+                    Ok(wine_path) => wine_path.to_string().into(),
+                    Err(_err) => {
+                        debug_event!(?arg, error = %_err, "could not convert argument");
+                        arg
+                    }
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,159 @@
+use std::path::PathBuf;
+
+use crate::{
+    CanonicalizePolicy, DriveMap, DrivePreference, FallbackDrive, WineConfig, WineFormatOptions,
+    WinePathError,
+};
+
+/// Builds a [`WineConfig`] with custom settings. Create one with [`WineConfig::builder`].
+///
+/// ```rust,no_run
+/// use winepath::{CanonicalizePolicy, WineConfig};
+/// let config = WineConfig::builder()
+///     .prefix("/home/username/.wine")
+///     .drive('d', "/mnt/data")
+///     .canonicalize(CanonicalizePolicy::Lexical)
+///     .fallback_drive('z')
+///     .build()
+///     .unwrap();
+/// let path = config.to_wine_path("/mnt/data/file.txt").unwrap();
+/// assert_eq!(path.to_string(), r"d:\file.txt");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WineConfigBuilder {
+    prefix: Option<PathBuf>,
+    drives: Vec<(char, PathBuf)>,
+    canonicalize_policy: CanonicalizePolicy,
+    format_options: WineFormatOptions,
+    case_insensitive: bool,
+    drive_preference: DrivePreference,
+    fallback_drive: FallbackDrive,
+    default_mappings: bool,
+    cache_capacity: usize,
+    #[cfg(unix)]
+    drive_cache: Option<PathBuf>,
+}
+
+impl WineConfigBuilder {
+    /// Create a builder with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given wine prefix. If it is not set, the prefix is determined from the
+    /// environment, like [`WineConfig::from_env`].
+    pub fn prefix(mut self, prefix: impl Into<PathBuf>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Map a drive to a native path, in addition to the drives in the prefix. This replaces the
+    /// mapping in the prefix if the drive is already mapped there.
+    ///
+    /// The path should be canonical, like in [`DriveMap::insert`]. Panics if `drive_letter` is
+    /// not an ASCII letter.
+    ///
+    /// [`DriveMap::insert`]: crate::DriveMap::insert
+    pub fn drive(mut self, drive_letter: char, path: impl Into<PathBuf>) -> Self {
+        assert!(drive_letter.is_ascii_alphabetic());
+        self.drives.push((drive_letter, path.into()));
+        self
+    }
+
+    /// Set the policy that [`WineConfig::to_wine_path`] uses to canonicalize native paths.
+    pub fn canonicalize(mut self, policy: CanonicalizePolicy) -> Self {
+        self.canonicalize_policy = policy;
+        self
+    }
+
+    /// Set the options for formatting wine paths. See [`WineConfig::set_format_options`].
+    pub fn format_options(mut self, options: WineFormatOptions) -> Self {
+        self.format_options = options;
+        self
+    }
+
+    /// Resolve native paths case-insensitively. See [`WineConfig::set_case_insensitive`].
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Set which drive to use when a native path is on several drives.
+    pub fn drive_preference(mut self, preference: DrivePreference) -> Self {
+        self.drive_preference = preference;
+        self
+    }
+
+    /// Use a drive for native paths that are not on a more specific drive, as if it were mapped
+    /// to `/`. The drive is only used if it is not mapped to something else. See
+    /// [`FallbackDrive::Drive`].
+    ///
+    /// Panics if `drive_letter` is not an ASCII letter.
+    pub fn fallback_drive(mut self, drive_letter: char) -> Self {
+        assert!(drive_letter.is_ascii_alphabetic());
+        self.fallback_drive = FallbackDrive::Drive(drive_letter.to_ascii_lowercase());
+        self
+    }
+
+    /// Only convert native paths that are on a drive that maps a specific directory, not on a
+    /// drive that maps `/`. See [`FallbackDrive::Disabled`].
+    pub fn no_fallback_drive(mut self) -> Self {
+        self.fallback_drive = FallbackDrive::Disabled;
+        self
+    }
+
+    /// Assume the default drive mappings if the prefix has no `dosdevices` directory yet. See
+    /// [`WineConfig::set_default_mappings`].
+    pub fn default_mappings(mut self, default_mappings: bool) -> Self {
+        self.default_mappings = default_mappings;
+        self
+    }
+
+    /// Cache up to `capacity` recent conversions. See [`WineConfig::set_cache_capacity`].
+    pub fn cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache_capacity = capacity;
+        self
+    }
+
+    /// Read the drive mappings from a cache file if it is up to date, and write it otherwise.
+    /// See [`WineConfig::load_drive_cache`].
+    ///
+    /// Errors writing the cache file are ignored; the config then works without a cache.
+    #[cfg(unix)]
+    pub fn drive_cache(mut self, cache_file: impl Into<PathBuf>) -> Self {
+        self.drive_cache = Some(cache_file.into());
+        self
+    }
+
+    /// Create the config.
+    ///
+    /// Returns [`WinePathError::PrefixNotFound`] if no prefix was set, and it could not be
+    /// determined from the environment.
+    pub fn build(self) -> Result<WineConfig, WinePathError> {
+        let mut config = match self.prefix {
+            Some(prefix) => WineConfig::from_prefix(prefix),
+            None => WineConfig::from_env()?,
+        };
+        config.canonicalize_policy = self.canonicalize_policy;
+        config.format_options = self.format_options;
+        config.case_insensitive = self.case_insensitive;
+        config.drive_preference = self.drive_preference;
+        config.fallback_drive = self.fallback_drive;
+        config.default_mappings = self.default_mappings;
+        config.set_cache_capacity(self.cache_capacity);
+        #[cfg(unix)]
+        if let Some(cache_file) = &self.drive_cache {
+            if !config.load_drive_cache(cache_file) {
+                if let Err(_err) = config.save_drive_cache(cache_file) {
+                    debug_event!(error = %_err, "could not write the drive cache");
+                }
+            }
+        }
+        if !self.drives.is_empty() {
+            let mut drive_map = DriveMap::clone(&config.drive_map());
+            drive_map.extend(self.drives);
+            config.set_drive_map(drive_map);
+        }
+        Ok(config)
+    }
+}
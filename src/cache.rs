@@ -0,0 +1,159 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::{DriveMap, WinePath};
+
+/// Statistics of a [`ConversionCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// The number of conversions that were answered from the cache.
+    pub hits: u64,
+    /// The number of conversions that were not in the cache.
+    pub misses: u64,
+    /// The number of cached conversions.
+    pub len: usize,
+    /// The maximum number of cached conversions.
+    pub capacity: usize,
+}
+
+/// A bounded cache of recent conversions, enabled with [`WineConfig::set_cache_capacity`].
+///
+/// When the cache is full, the least recently used conversion is dropped. The cache is cleared
+/// automatically when the drive mappings change. Only successful conversions of absolute paths
+/// are cached.
+///
+/// [`WineConfig::set_cache_capacity`]: crate::WineConfig::set_cache_capacity
+#[derive(Debug)]
+pub struct ConversionCache {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum CacheKey {
+    /// A native path that was converted with `to_wine_path`.
+    Native(PathBuf),
+    /// A wine path that was converted with `to_native_path`.
+    Wine(String),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum CacheValue {
+    Wine(WinePath),
+    Native(PathBuf),
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// The drive mappings that the cached conversions were made with.
+    drive_map: Option<Arc<DriveMap>>,
+    /// The cached conversions, with the tick of their last use.
+    entries: HashMap<CacheKey, (CacheValue, u64)>,
+    /// The keys of the cached conversions, least recently used first.
+    recent: BTreeMap<u64, CacheKey>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl ConversionCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Get the maximum number of cached conversions.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the hit and miss counts and the size of the cache.
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.lock();
+        CacheStats {
+            hits: inner.hits,
+            misses: inner.misses,
+            len: inner.entries.len(),
+            capacity: self.capacity,
+        }
+    }
+
+    /// Drop all cached conversions. The hit and miss counts are kept.
+    ///
+    /// Call this if the file system changed in a way that affects
+    /// [canonicalization][crate::CanonicalizePolicy] or
+    /// [case-insensitive lookups][crate::WineConfig::set_case_insensitive].
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.entries.clear();
+        inner.recent.clear();
+    }
+
+    /// Look up a conversion that was made with `drive_map`, and mark it as recently used.
+    pub(crate) fn get(&self, drive_map: &Arc<DriveMap>, key: &CacheKey) -> Option<CacheValue> {
+        let mut inner = self.lock();
+        let inner = &mut *inner;
+        if !inner
+            .drive_map
+            .as_ref()
+            .is_some_and(|cached| Arc::ptr_eq(cached, drive_map))
+        {
+            trace_event!("drive mappings changed, clearing the conversion cache");
+            inner.drive_map = Some(Arc::clone(drive_map));
+            inner.entries.clear();
+            inner.recent.clear();
+        }
+        inner.tick += 1;
+        let tick = inner.tick;
+        match inner.entries.get_mut(key) {
+            Some((value, last_used)) => {
+                inner.hits += 1;
+                let key = inner.recent.remove(last_used);
+                inner
+                    .recent
+                    .insert(tick, key.expect("cache entry has a tick"));
+                *last_used = tick;
+                Some(value.clone())
+            }
+            None => {
+                inner.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store a conversion that was made with `drive_map`, dropping the least recently used
+    /// conversion if the cache is full.
+    pub(crate) fn insert(&self, drive_map: &Arc<DriveMap>, key: CacheKey, value: CacheValue) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.lock();
+        let inner = &mut *inner;
+        // The drive mappings changed while converting; the result may be outdated.
+        if !inner
+            .drive_map
+            .as_ref()
+            .is_some_and(|cached| Arc::ptr_eq(cached, drive_map))
+        {
+            return;
+        }
+        inner.tick += 1;
+        let tick = inner.tick;
+        if let Some((_, last_used)) = inner.entries.insert(key.clone(), (value, tick)) {
+            inner.recent.remove(&last_used);
+        } else if inner.entries.len() > self.capacity {
+            if let Some((_, oldest)) = inner.recent.pop_first() {
+                inner.entries.remove(&oldest);
+            }
+        }
+        inner.recent.insert(tick, key);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
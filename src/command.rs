@@ -0,0 +1,130 @@
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::os::unix::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{NativePath, PathMapper, WinePath, WinePathError};
+
+/// A [`PathMapper`] that converts paths by running the real `winepath` program.
+///
+/// This spawns a process for every conversion, and starts the wineserver if it is not running, so
+/// it is a lot slower than [`WineConfig`][crate::WineConfig]. Use it where matching Wine's own
+/// behaviour exactly matters more than speed, or as a fallback for paths that
+/// [`WineConfig`][crate::WineConfig] does not support.
+///
+/// ```rust,no_run
+/// use winepath::{PathMapper, WineConfig, WinepathCommand};
+/// use std::path::Path;
+/// let config = WineConfig::from_env().unwrap();
+/// let command = WinepathCommand::new().prefix(config.prefix());
+/// let path = Path::new("/home/username/.wine/drive_c/windows");
+/// let wine_path = config.to_wine(path).or_else(|_| command.to_wine(path)).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct WinepathCommand {
+    program: OsString,
+    args: Vec<OsString>,
+    prefix: Option<PathBuf>,
+}
+
+impl WinepathCommand {
+    /// Run the `winepath` program from `PATH`.
+    pub fn new() -> Self {
+        Self::with_program("winepath", Vec::<OsString>::new())
+    }
+
+    /// Run `wine winepath.exe`, for Wine installations that do not have a `winepath` program.
+    pub fn wine() -> Self {
+        Self::with_program("wine", vec!["winepath.exe"])
+    }
+
+    /// Run a custom program, with `args` before the `winepath` options. For example, the
+    /// `winepath` from a specific Wine build, or `wine64 winepath.exe`.
+    pub fn with_program(
+        program: impl AsRef<OsStr>,
+        args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+    ) -> Self {
+        Self {
+            program: program.as_ref().to_os_string(),
+            args: args
+                .into_iter()
+                .map(|arg| arg.as_ref().to_os_string())
+                .collect(),
+            prefix: None,
+        }
+    }
+
+    /// Use the given wine prefix, by setting `WINEPREFIX`. By default, the prefix is inherited
+    /// from the environment.
+    pub fn prefix(mut self, prefix: impl AsRef<Path>) -> Self {
+        self.prefix = Some(prefix.as_ref().to_path_buf());
+        self
+    }
+
+    /// Run the program with an option (`-w` or `-u`) and a path, and return its output without
+    /// the trailing newline.
+    fn run(&self, option: &str, path: &OsStr) -> Result<Vec<u8>, WinePathError> {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args).arg(option).arg(path);
+        // Keep Wine's debug messages out of stderr.
+        command.env("WINEDEBUG", "-all");
+        if let Some(prefix) = &self.prefix {
+            command.env("WINEPREFIX", prefix);
+        }
+        debug_event!(?command, "running winepath");
+        let output = command.output()?;
+        if !output.status.success() {
+            return Err(WinePathError::Io(io::Error::other(format!(
+                "{} failed with {}: {}",
+                self.program.to_string_lossy(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))));
+        }
+        let mut stdout = output.stdout;
+        while stdout
+            .last()
+            .is_some_and(|&byte| byte == b'\n' || byte == b'\r')
+        {
+            stdout.pop();
+        }
+        Ok(stdout)
+    }
+}
+
+impl Default for WinepathCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PathMapper for WinepathCommand {
+    fn to_wine(&self, path: &NativePath) -> Result<WinePath, WinePathError> {
+        let output = self.run("-w", path.as_os_str())?;
+        let output = String::from_utf8(output).map_err(|_| {
+            WinePathError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "winepath printed a path that is not valid UTF-8",
+            ))
+        })?;
+        // winepath prints paths outside all drives in the `\\?\unix` namespace.
+        if output.is_empty() || output.starts_with(r"\\?\unix\") {
+            return Err(WinePathError::NoDrive {
+                path: path.to_path_buf(),
+                mapped: vec![],
+            });
+        }
+        Ok(WinePath(output))
+    }
+
+    fn to_native(&self, path: &WinePath) -> Result<PathBuf, WinePathError> {
+        let output = self.run("-u", OsStr::new(path.as_ref()))?;
+        if output.is_empty() {
+            return Err(WinePathError::InvalidWinePath {
+                path: path.to_string(),
+            });
+        }
+        Ok(PathBuf::from(OsString::from_vec(output)))
+    }
+}
@@ -0,0 +1,186 @@
+use std::path::PathBuf;
+
+use crate::wine_path::split_root;
+use crate::{drive_to_index, NativePath, WineConfig, WinePath, WinePathError, WinePathKind};
+
+/// A current wine directory, for converting relative paths.
+///
+/// Relative wine paths are resolved against the current directory, like in a shell working
+/// inside the prefix. Relative native paths are resolved against the native equivalent of the
+/// current directory.
+///
+/// Like on Windows, every drive has its own current directory, which is used for drive-relative
+/// paths like `d:report.doc`.
+///
+/// ```rust,no_run
+/// use winepath::{WineConfig, WineCwd};
+/// use std::path::PathBuf;
+/// let config = WineConfig::from_env().unwrap();
+/// let mut cwd = WineCwd::new(&config, r"c:\Program Files").unwrap();
+/// cwd.set_cwd("CoolApp").unwrap();
+/// assert_eq!(cwd.cwd().to_string(), r"c:\Program Files\CoolApp");
+/// let path = cwd.to_native_path(r"data\save.dat").unwrap();
+/// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c/Program Files/CoolApp/data/save.dat"));
+/// let path = cwd.to_wine_path("data/save.dat").unwrap();
+/// assert_eq!(path.to_string(), r"c:\Program Files\CoolApp\data\save.dat");
+/// ```
+#[derive(Debug, Clone)]
+pub struct WineCwd<'a> {
+    config: &'a WineConfig,
+    cwd: WinePath,
+    drive_cwds: [Option<WinePath>; 26],
+}
+
+/// Check if a wine path can be used as a current directory.
+fn is_absolute(path: &WinePath) -> bool {
+    matches!(
+        path.kind(),
+        WinePathKind::Absolute { .. } | WinePathKind::Unc
+    )
+}
+
+impl<'a> WineCwd<'a> {
+    /// Start a session in the directory `cwd`.
+    ///
+    /// Returns [`WinePathError::InvalidWinePath`] if `cwd` is not an absolute drive or UNC path.
+    pub fn new(config: &'a WineConfig, cwd: impl Into<WinePath>) -> Result<Self, WinePathError> {
+        let cwd = cwd.into().normalize();
+        if !is_absolute(&cwd) {
+            return Err(WinePathError::InvalidWinePath { path: cwd.0 });
+        }
+        let mut session = Self {
+            config,
+            cwd: cwd.clone(),
+            drive_cwds: Default::default(),
+        };
+        session.remember_drive_cwd(cwd);
+        Ok(session)
+    }
+
+    /// Store `cwd` as the current directory of its drive.
+    fn remember_drive_cwd(&mut self, cwd: WinePath) {
+        if let WinePathKind::Absolute { drive } = cwd.kind() {
+            self.drive_cwds[drive_to_index(drive)] = Some(cwd);
+        }
+    }
+
+    /// Get the config that is used for conversions.
+    pub fn config(&self) -> &'a WineConfig {
+        self.config
+    }
+
+    /// Get the current wine directory.
+    pub fn cwd(&self) -> &WinePath {
+        &self.cwd
+    }
+
+    /// Change the current directory, like `cd`. Relative paths are resolved against the current
+    /// directory.
+    ///
+    /// The directory does not need to exist. The previous drive keeps its current directory, so
+    /// `set_cwd("c:")` switches back to it, like in `cmd.exe`.
+    ///
+    /// ```rust
+    /// use winepath::{WineConfig, WineCwd};
+    /// let config = WineConfig::from_prefix("/home/username/.wine");
+    /// let mut cwd = WineCwd::new(&config, r"c:\windows").unwrap();
+    /// cwd.set_cwd(r"d:\games").unwrap();
+    /// cwd.set_cwd("c:").unwrap();
+    /// assert_eq!(cwd.cwd().to_string(), r"c:\windows");
+    /// ```
+    pub fn set_cwd(&mut self, path: impl Into<WinePath>) -> Result<(), WinePathError> {
+        let cwd = self.resolve(path);
+        if !is_absolute(&cwd) {
+            return Err(WinePathError::InvalidWinePath { path: cwd.0 });
+        }
+        self.cwd = cwd.clone();
+        self.remember_drive_cwd(cwd);
+        Ok(())
+    }
+
+    /// Get the current directory of a drive. This is the drive root if it was never set.
+    ///
+    /// Panics if `drive` is not an ASCII letter.
+    pub fn drive_cwd(&self, drive: char) -> WinePath {
+        self.drive_cwds[drive_to_index(drive)]
+            .clone()
+            .unwrap_or_else(|| WinePath(format!(r"{}:\", drive.to_ascii_lowercase())))
+    }
+
+    /// Set the current directory of a drive, without switching to it.
+    ///
+    /// This is like the hidden `=D:` environment variables that Windows uses to track them.
+    /// Returns [`WinePathError::InvalidWinePath`] if `path` is not an absolute path on `drive`.
+    /// Panics if `drive` is not an ASCII letter.
+    ///
+    /// ```rust
+    /// use winepath::{WineConfig, WineCwd};
+    /// let config = WineConfig::from_prefix("/home/username/.wine");
+    /// let mut cwd = WineCwd::new(&config, r"c:\windows").unwrap();
+    /// cwd.set_drive_cwd('d', r"d:\games\CoolGame").unwrap();
+    /// assert_eq!(cwd.resolve("d:save.dat").to_string(), r"d:\games\CoolGame\save.dat");
+    /// assert_eq!(cwd.resolve("c:system32").to_string(), r"c:\windows\system32");
+    /// ```
+    pub fn set_drive_cwd(
+        &mut self,
+        drive: char,
+        path: impl Into<WinePath>,
+    ) -> Result<(), WinePathError> {
+        let path = path.into().normalize();
+        match path.kind() {
+            WinePathKind::Absolute { drive: path_drive }
+                if path_drive.eq_ignore_ascii_case(&drive) => {}
+            _ => return Err(WinePathError::InvalidWinePath { path: path.0 }),
+        }
+        if let WinePathKind::Absolute { drive: cwd_drive } = self.cwd.kind() {
+            if cwd_drive.eq_ignore_ascii_case(&drive) {
+                self.cwd = path.clone();
+            }
+        }
+        self.remember_drive_cwd(path);
+        Ok(())
+    }
+
+    /// Resolve a wine path against the current directory, and [normalize][WinePath::normalize]
+    /// it.
+    ///
+    /// Absolute paths are only normalized. Paths like `\windows` are placed on the current drive.
+    /// Drive-relative paths like `d:save.dat` are resolved against the current directory of that
+    /// drive, see [`WineCwd::drive_cwd`].
+    ///
+    /// ```rust
+    /// use winepath::{WineConfig, WineCwd};
+    /// let config = WineConfig::from_prefix("/home/username/.wine");
+    /// let cwd = WineCwd::new(&config, r"c:\users\username").unwrap();
+    /// assert_eq!(cwd.resolve(r"..\Public").to_string(), r"c:\users\Public");
+    /// assert_eq!(cwd.resolve(r"\windows").to_string(), r"c:\windows");
+    /// assert_eq!(cwd.resolve(r"d:\games").to_string(), r"d:\games");
+    /// ```
+    pub fn resolve(&self, path: impl Into<WinePath>) -> WinePath {
+        let path = path.into();
+        match path.kind() {
+            WinePathKind::DriveRelative { drive } => {
+                let (_, rest, _) = split_root(&path.0);
+                self.drive_cwd(drive).join(rest).normalize()
+            }
+            _ => self.cwd.join(path).normalize(),
+        }
+    }
+
+    /// Convert a wine path to a native path, resolving relative paths against the current
+    /// directory. See [`WineConfig::to_native_path`].
+    pub fn to_native_path(&self, path: impl Into<WinePath>) -> Result<PathBuf, WinePathError> {
+        self.config.to_native_path(self.resolve(path))
+    }
+
+    /// Convert a native path to a wine path, resolving relative paths against the native
+    /// equivalent of the current directory. See [`WineConfig::to_wine_path`].
+    pub fn to_wine_path(&self, path: impl AsRef<NativePath>) -> Result<WinePath, WinePathError> {
+        let path = path.as_ref();
+        if path.is_absolute() {
+            return self.config.to_wine_path(path);
+        }
+        let native_cwd = self.config.to_native_path(self.cwd.clone())?;
+        self.config.to_wine_path(native_cwd.join(path))
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+
+use crate::NativePath;
+
+/// A problem with a wine prefix, found by [`WineConfig::diagnose`].
+///
+/// [`WineConfig::diagnose`]: crate::WineConfig::diagnose
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// The prefix directory does not exist.
+    PrefixMissing,
+    /// The prefix directory has no `dosdevices` directory and no registry files, so it is
+    /// probably not a wine prefix.
+    NotAPrefix,
+    /// The prefix has no `dosdevices` directory, e.g. because it was not initialized by
+    /// `wineboot` yet. No drives are mapped.
+    NoDosdevices,
+    /// A drive that most programs expect is not mapped: `c:`, or `z:` for the native root.
+    MissingDrive {
+        /// The drive letter.
+        letter: char,
+    },
+    /// An entry for a drive in the `dosdevices` directory is not a symlink or a directory, so it
+    /// is ignored.
+    NotASymlink {
+        /// The drive letter.
+        letter: char,
+    },
+    /// A drive symlink points to a path that does not exist, e.g. an unmounted disk. The drive
+    /// is still mapped, but [offline][crate::DriveMap::is_offline].
+    BrokenDriveLink {
+        /// The drive letter.
+        letter: char,
+        /// The target of the symlink.
+        target: PathBuf,
+    },
+    /// A drive symlink points to a path that is not valid UTF-8. Wine paths on the drive can
+    /// still be converted, but the drive root can't be shown as a string.
+    NonUtf8Target {
+        /// The drive letter.
+        letter: char,
+        /// The target of the symlink.
+        target: PathBuf,
+    },
+    /// A UNC share symlink in `dosdevices/unc` points to a path that does not exist.
+    BrokenUncLink {
+        /// The server name.
+        server: String,
+        /// The share name.
+        share: String,
+        /// The target of the symlink.
+        target: PathBuf,
+    },
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::PrefixMissing => write!(f, "prefix directory does not exist"),
+            Diagnostic::NotAPrefix => write!(f, "directory does not look like a wine prefix"),
+            Diagnostic::NoDosdevices => write!(f, "prefix has no dosdevices directory"),
+            Diagnostic::MissingDrive { letter } => write!(f, "drive {}: is not mapped", letter),
+            Diagnostic::NotASymlink { letter } => {
+                write!(
+                    f,
+                    "dosdevices entry for drive {}: is not a symlink or a directory",
+                    letter
+                )
+            }
+            Diagnostic::BrokenDriveLink { letter, target } => write!(
+                f,
+                "drive {}: points to {}, which does not exist",
+                letter,
+                target.display()
+            ),
+            Diagnostic::NonUtf8Target { letter, target } => write!(
+                f,
+                "drive {}: points to {}, which is not valid UTF-8",
+                letter,
+                target.display()
+            ),
+            Diagnostic::BrokenUncLink {
+                server,
+                share,
+                target,
+            } => write!(
+                f,
+                r"share \\{}\{} points to {}, which does not exist",
+                server,
+                share,
+                target.display()
+            ),
+        }
+    }
+}
+
+/// Check a drive entry in the `dosdevices` directory.
+fn diagnose_drive(drives_dir: &NativePath, letter: char, diagnostics: &mut Vec<Diagnostic>) {
+    let link = drives_dir.join(format!("{}:", letter));
+    match link.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => return,
+        Ok(_) => {}
+        Err(_) => return,
+    }
+    let target = match link.read_link() {
+        Ok(target) => target,
+        Err(_) => {
+            diagnostics.push(Diagnostic::NotASymlink { letter });
+            return;
+        }
+    };
+    if !drives_dir.join(&target).exists() {
+        diagnostics.push(Diagnostic::BrokenDriveLink { letter, target });
+    } else if target.to_str().is_none() {
+        diagnostics.push(Diagnostic::NonUtf8Target { letter, target });
+    }
+}
+
+/// Check the UNC share symlinks in the `dosdevices/unc` directory.
+fn diagnose_unc_shares(unc_dir: &NativePath, diagnostics: &mut Vec<Diagnostic>) {
+    let servers = match unc_dir.read_dir() {
+        Ok(servers) => servers,
+        Err(_) => return,
+    };
+    for server in servers.filter_map(Result::ok) {
+        let shares = match server.path().read_dir() {
+            Ok(shares) => shares,
+            Err(_) => continue,
+        };
+        for share in shares.filter_map(Result::ok) {
+            let target = match share.path().read_link() {
+                Ok(target) => target,
+                Err(_) => continue,
+            };
+            if !server.path().join(&target).exists() {
+                diagnostics.push(Diagnostic::BrokenUncLink {
+                    server: server.file_name().to_string_lossy().into_owned(),
+                    share: share.file_name().to_string_lossy().into_owned(),
+                    target,
+                });
+            }
+        }
+    }
+}
+
+/// Check a prefix for problems that would break path conversions.
+pub(crate) fn diagnose(prefix: &NativePath) -> Vec<Diagnostic> {
+    if !prefix.is_dir() {
+        return vec![Diagnostic::PrefixMissing];
+    }
+    let drives_dir = prefix.join("dosdevices");
+    if !drives_dir.is_dir() {
+        return if prefix.join("system.reg").is_file() {
+            vec![Diagnostic::NoDosdevices]
+        } else {
+            vec![Diagnostic::NotAPrefix]
+        };
+    }
+
+    let mut diagnostics = vec![];
+    for letter in b'a'..=b'z' {
+        diagnose_drive(&drives_dir, char::from(letter), &mut diagnostics);
+    }
+    for letter in ['c', 'z'] {
+        // Broken or invalid entries were already reported above.
+        if drives_dir
+            .join(format!("{}:", letter))
+            .symlink_metadata()
+            .is_err()
+        {
+            diagnostics.push(Diagnostic::MissingDrive { letter });
+        }
+    }
+    diagnose_unc_shares(&drives_dir.join("unc"), &mut diagnostics);
+    diagnostics
+}
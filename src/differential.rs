@@ -0,0 +1,221 @@
+//! Compare the conversions of this crate with the real `winepath` program, to find paths that
+//! this crate converts differently from Wine.
+//!
+//! Only available with the `differential` feature.
+//!
+//! ```rust,no_run
+//! use winepath::WineConfig;
+//! let config = WineConfig::from_env().unwrap();
+//! let report = winepath::differential::compare_prefix(&config);
+//! println!("{}", report);
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{PathMapper, WineConfig, WinePath, WinePathError, WinepathCommand};
+
+/// A path that was converted differently by the two [`PathMapper`]s, in a [`Report`].
+#[derive(Debug)]
+pub enum Mismatch {
+    /// A native path was converted to a different wine path.
+    ToWine {
+        /// The native path.
+        input: PathBuf,
+        /// The result of the mapper under test.
+        actual: Result<WinePath, WinePathError>,
+        /// The result of the reference mapper.
+        expected: Result<WinePath, WinePathError>,
+    },
+    /// A wine path was converted to a different native path.
+    ToNative {
+        /// The wine path.
+        input: WinePath,
+        /// The result of the mapper under test.
+        actual: Result<PathBuf, WinePathError>,
+        /// The result of the reference mapper.
+        expected: Result<PathBuf, WinePathError>,
+    },
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn result<T>(result: &Result<T, WinePathError>, display: impl Fn(&T) -> String) -> String {
+            match result {
+                Ok(value) => display(value),
+                Err(err) => format!("error: {}", err),
+            }
+        }
+        match self {
+            Mismatch::ToWine {
+                input,
+                actual,
+                expected,
+            } => write!(
+                f,
+                "{}: got {}, expected {}",
+                input.display(),
+                result(actual, WinePath::to_string),
+                result(expected, WinePath::to_string)
+            ),
+            Mismatch::ToNative {
+                input,
+                actual,
+                expected,
+            } => write!(
+                f,
+                "{}: got {}, expected {}",
+                input,
+                result(actual, |path| path.display().to_string()),
+                result(expected, |path| path.display().to_string())
+            ),
+        }
+    }
+}
+
+/// The outcome of a comparison.
+///
+/// The [`Display`] implementation renders a summary line, followed by one line per mismatch.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// The number of paths that were compared.
+    pub checked: usize,
+    /// The paths that were converted differently.
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl Report {
+    /// Whether all paths were converted the same.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// Add the results of another comparison to this report.
+    pub fn merge(&mut self, other: Report) {
+        self.checked += other.checked;
+        self.mismatches.extend(other.mismatches);
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} paths converted differently",
+            self.mismatches.len(),
+            self.checked
+        )?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n  {}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+/// Convert native paths to wine paths with both mappers, and report the paths where the results
+/// differ.
+///
+/// Wine paths are compared ignoring case, like [`WinePath`]'s [`PartialEq`] does. If both mappers
+/// return an error, the path counts as a match, even if the errors are different.
+pub fn compare_to_wine(
+    actual: &(impl PathMapper + ?Sized),
+    expected: &(impl PathMapper + ?Sized),
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Report {
+    let mut report = Report::default();
+    for path in paths {
+        let path = path.as_ref();
+        let actual = actual.to_wine(path);
+        let expected = expected.to_wine(path);
+        report.checked += 1;
+        if !same_result(&actual, &expected, |a, b| a == b) {
+            debug_event!(?path, "to_wine mismatch");
+            report.mismatches.push(Mismatch::ToWine {
+                input: path.to_path_buf(),
+                actual,
+                expected,
+            });
+        }
+    }
+    report
+}
+
+/// Convert wine paths to native paths with both mappers, and report the paths where the results
+/// differ.
+///
+/// `winepath` returns paths through the `dosdevices` directory, so native paths that exist are
+/// compared after resolving symlinks. If both mappers return an error, the path counts as a match,
+/// even if the errors are different.
+pub fn compare_to_native(
+    actual: &(impl PathMapper + ?Sized),
+    expected: &(impl PathMapper + ?Sized),
+    paths: impl IntoIterator<Item = impl Into<WinePath>>,
+) -> Report {
+    let mut report = Report::default();
+    for path in paths {
+        let path = path.into();
+        let actual = actual.to_native(&path);
+        let expected = expected.to_native(&path);
+        report.checked += 1;
+        if !same_result(&actual, &expected, same_native_path) {
+            debug_event!(%path, "to_native mismatch");
+            report.mismatches.push(Mismatch::ToNative {
+                input: path,
+                actual,
+                expected,
+            });
+        }
+    }
+    report
+}
+
+/// Compare `config` with the `winepath` program on the paths in its prefix.
+///
+/// This checks the prefix itself, the root of every drive, and the entries directly inside each
+/// drive root, in both directions. Drives that map the whole file system only have their root
+/// checked. Every path spawns two `winepath` processes, so this can take a while.
+pub fn compare_prefix(config: &WineConfig) -> Report {
+    let command = WinepathCommand::new().prefix(config.prefix());
+    let mut native_paths = vec![config.prefix().to_path_buf()];
+    let mut wine_paths = vec![];
+    for (letter, root) in config.drives() {
+        wine_paths.push(WinePath(format!(r"{}:\", letter)));
+        if root != Path::new("/") {
+            if let Ok(entries) = fs::read_dir(&root) {
+                for entry in entries.flatten() {
+                    if let Some(name) = entry.file_name().to_str() {
+                        wine_paths.push(WinePath(format!(r"{}:\{}", letter, name)));
+                    }
+                    native_paths.push(entry.path());
+                }
+            }
+        }
+        native_paths.push(root);
+    }
+    let mut report = compare_to_wine(config, &command, native_paths);
+    report.merge(compare_to_native(config, &command, wine_paths));
+    report
+}
+
+fn same_result<T>(
+    actual: &Result<T, WinePathError>,
+    expected: &Result<T, WinePathError>,
+    same: impl Fn(&T, &T) -> bool,
+) -> bool {
+    match (actual, expected) {
+        (Ok(actual), Ok(expected)) => same(actual, expected),
+        (Err(_), Err(_)) => true,
+        _ => false,
+    }
+}
+
+fn same_native_path(actual: &PathBuf, expected: &PathBuf) -> bool {
+    if actual == expected {
+        return true;
+    }
+    match (fs::canonicalize(actual), fs::canonicalize(expected)) {
+        (Ok(actual), Ok(expected)) => actual == expected,
+        _ => false,
+    }
+}
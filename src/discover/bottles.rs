@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use super::{home_dir, subdirectories, xdg_dir, yaml_value};
+use crate::WineArch;
+
+/// A bottle managed by Bottles. See [`bottles`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bottle {
+    /// The name of the bottle.
+    pub name: String,
+    /// The runner of the bottle, like `soda-7.0-9`.
+    pub runner: Option<String>,
+    /// The architecture of the bottle, if it is configured.
+    pub arch: Option<WineArch>,
+    /// The wine prefix of the bottle.
+    pub prefix: PathBuf,
+}
+
+/// Directories that Bottles keeps bottles in, for the Flatpak and the native versions.
+fn bottles_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Some(home) = home_dir() {
+        dirs.push(home.join(".var/app/com.usebottles.bottles/data/bottles/bottles"));
+    }
+    dirs.extend(xdg_dir("XDG_DATA_HOME", ".local/share").map(|dir| dir.join("bottles/bottles")));
+    dirs
+}
+
+/// Read the `bottle.yml` file of a bottle. Returns `None` if it is not a bottle.
+fn read_bottle(prefix: PathBuf) -> Option<Bottle> {
+    let contents = std::fs::read_to_string(prefix.join("bottle.yml")).ok()?;
+    let name = yaml_value(&contents, None, "Name")
+        .or_else(|| Some(prefix.file_name()?.to_str()?.to_string()))?;
+    let arch = yaml_value(&contents, None, "Arch").and_then(|arch| WineArch::from_name(&arch));
+    Some(Bottle {
+        name,
+        runner: yaml_value(&contents, None, "Runner"),
+        arch,
+        prefix,
+    })
+}
+
+/// Find the bottles managed by Bottles, including the Flatpak version.
+///
+/// Each bottle is a wine prefix, with its configuration in a `bottle.yml` file.
+///
+/// ```rust,no_run
+/// use winepath::{discover::bottles, WineConfig};
+/// for bottle in bottles() {
+///     let config = WineConfig::from_prefix(&bottle.prefix);
+///     println!("{}: {}", bottle.name, config.prefix().display());
+/// }
+/// ```
+pub fn bottles() -> Vec<Bottle> {
+    bottles_dirs()
+        .iter()
+        .flat_map(|dir| subdirectories(dir))
+        .filter_map(read_bottle)
+        .collect()
+}
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use super::{home_dir, subdirectories};
+use crate::registry::RegistryFile;
+use crate::WineArch;
+
+/// A bottle managed by CrossOver. See [`crossover_bottles`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossOverBottle {
+    /// The name of the bottle.
+    pub name: String,
+    /// The description of the bottle, if it has one.
+    pub description: Option<String>,
+    /// The template that the bottle was created from, like `win10_64`.
+    pub template: Option<String>,
+    /// The architecture of the bottle.
+    pub arch: Option<WineArch>,
+    /// The wine prefix of the bottle.
+    pub prefix: PathBuf,
+}
+
+/// Read a value from the `[Bottle]` section of a `cxbottle.conf` file. Lines look like
+/// `"Template" = "win10_64"`.
+fn cxbottle_value(contents: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_section = line == "[Bottle]";
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        let (name, value) = match line.split_once('=') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        if name.trim().trim_matches('"') == key {
+            let value = value.trim().trim_matches('"');
+            return Some(value.to_string()).filter(|value| !value.is_empty());
+        }
+    }
+    None
+}
+
+/// Read the `cxbottle.conf` file of a CrossOver bottle. Returns `None` if it is not a bottle.
+fn read_crossover_bottle(prefix: PathBuf) -> Option<CrossOverBottle> {
+    let contents = std::fs::read_to_string(prefix.join("cxbottle.conf")).ok()?;
+    let arch = RegistryFile::read(&prefix, "system.reg")
+        .and_then(|registry| WineArch::from_registry(&registry));
+    Some(CrossOverBottle {
+        name: prefix.file_name()?.to_str()?.to_string(),
+        description: cxbottle_value(&contents, "Description"),
+        template: cxbottle_value(&contents, "Template"),
+        arch,
+        prefix,
+    })
+}
+
+/// Find the bottles managed by CrossOver.
+///
+/// Bottles are read from `CX_BOTTLE_PATH` if it is set, and otherwise from `~/.cxoffice` on
+/// Linux and `~/Library/Application Support/CrossOver/Bottles` on macOS.
+///
+/// ```rust,no_run
+/// use winepath::{discover::crossover_bottles, WineConfig};
+/// for bottle in crossover_bottles() {
+///     let config = WineConfig::from_prefix(&bottle.prefix);
+///     println!("{}: {}", bottle.name, config.prefix().display());
+/// }
+/// ```
+pub fn crossover_bottles() -> Vec<CrossOverBottle> {
+    let dirs: Vec<PathBuf> = match std::env::var_os("CX_BOTTLE_PATH") {
+        Some(paths) => std::env::split_paths(&paths).collect(),
+        None => match home_dir() {
+            Some(home) => vec![
+                home.join(".cxoffice"),
+                home.join("Library/Application Support/CrossOver/Bottles"),
+            ],
+            None => vec![],
+        },
+    };
+    dirs.iter()
+        .flat_map(|dir| subdirectories(dir))
+        .filter_map(read_crossover_bottle)
+        .collect()
+}
@@ -0,0 +1,109 @@
+use std::path::PathBuf;
+
+use super::{home_dir, xdg_dir, yaml_value};
+use crate::{NativePath, WineArch};
+
+/// Expand a leading `~` in a path to the home directory.
+fn expand_home(path: &str) -> Option<PathBuf> {
+    match path.strip_prefix('~') {
+        Some(rest) => Some(home_dir()?.join(rest.trim_start_matches('/'))),
+        None => Some(PathBuf::from(path)),
+    }
+}
+
+/// List the files in a directory with an extension, sorted by name.
+fn files_with_extension(dir: &NativePath, extension: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = match dir.read_dir() {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+            .collect(),
+        Err(_) => vec![],
+    };
+    files.sort();
+    files
+}
+
+/// A game configured in Lutris to run with Wine. See [`lutris_games`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LutrisGame {
+    /// The name of the game, if it is in the game configuration.
+    pub name: Option<String>,
+    /// The identifier of the game, like `cool-game`.
+    pub slug: String,
+    /// The wine prefix of the game.
+    pub prefix: PathBuf,
+    /// The architecture of the prefix, if it is configured.
+    pub arch: Option<WineArch>,
+    /// The Wine version that Lutris uses for the game, like `lutris-7.2-2-x86_64`.
+    pub wine_version: Option<String>,
+    /// The game configuration file.
+    pub config_file: PathBuf,
+}
+
+/// Directories that Lutris keeps game configuration files in. Older versions of Lutris used the
+/// config directory instead of the data directory. The Flatpak version uses its own.
+fn lutris_game_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    dirs.extend(xdg_dir("XDG_DATA_HOME", ".local/share").map(|dir| dir.join("lutris/games")));
+    dirs.extend(xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("lutris/games")));
+    if let Some(home) = home_dir() {
+        let flatpak = home.join(".var/app/net.lutris.Lutris");
+        dirs.push(flatpak.join("data/lutris/games"));
+        dirs.push(flatpak.join("config/lutris/games"));
+    }
+    dirs
+}
+
+/// Read a Lutris game configuration file. Returns `None` if the game doesn't have a wine prefix.
+fn read_lutris_game(config_file: PathBuf) -> Option<LutrisGame> {
+    let contents = std::fs::read_to_string(&config_file).ok()?;
+    let runner = yaml_value(&contents, None, "runner");
+    if runner.as_deref().is_some_and(|runner| runner != "wine") {
+        return None;
+    }
+    let prefix = expand_home(&yaml_value(&contents, Some("game"), "prefix")?)?;
+    let arch =
+        yaml_value(&contents, Some("game"), "arch").and_then(|arch| WineArch::from_name(&arch));
+    // Configuration files are named after the slug and the install time, like
+    // `cool-game-1700000000.yml`.
+    let slug = yaml_value(&contents, None, "game_slug")
+        .or_else(|| yaml_value(&contents, None, "slug"))
+        .or_else(|| {
+            let stem = config_file.file_stem()?.to_str()?;
+            let slug = match stem.rsplit_once('-') {
+                Some((slug, time)) if time.bytes().all(|b| b.is_ascii_digit()) => slug,
+                _ => stem,
+            };
+            Some(slug.to_string())
+        })?;
+    Some(LutrisGame {
+        name: yaml_value(&contents, None, "name"),
+        slug,
+        prefix,
+        arch,
+        wine_version: yaml_value(&contents, Some("wine"), "version"),
+        config_file,
+    })
+}
+
+/// Find the games that are configured in Lutris to run with Wine, and their prefixes.
+///
+/// Games are read from the YAML configuration files of Lutris, including the Flatpak version.
+/// Games that use other runners, or that have no wine prefix configured, are skipped.
+///
+/// ```rust,no_run
+/// use winepath::{discover::lutris_games, WineConfig};
+/// for game in lutris_games() {
+///     let config = WineConfig::from_prefix(&game.prefix);
+///     println!("{}: {}", game.name.as_ref().unwrap_or(&game.slug), config.prefix().display());
+/// }
+/// ```
+pub fn lutris_games() -> Vec<LutrisGame> {
+    lutris_game_dirs()
+        .iter()
+        .flat_map(|dir| files_with_extension(dir, "yml"))
+        .filter_map(read_lutris_game)
+        .collect()
+}
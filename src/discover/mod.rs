@@ -0,0 +1,247 @@
+//! Find the wine prefixes that are managed by Wine frontends, like Lutris, Bottles,
+//! PlayOnLinux and CrossOver.
+//!
+//! The prefixes are found in the configuration files of the frontends in the home directory of
+//! the current user. Create a [`WineConfig`][crate::WineConfig] for a prefix with
+//! [`WineConfig::from_prefix`][crate::WineConfig::from_prefix].
+//!
+//! [`discover_prefixes`] finds the prefixes of all frontends at once. Each frontend can be
+//! disabled with its feature flag: `discover-bottles`, `discover-crossover`, `discover-lutris`,
+//! `discover-playonlinux` and `discover-steam`. They are all enabled by default.
+
+use std::path::PathBuf;
+
+use crate::registry::RegistryFile;
+use crate::WineArch;
+
+#[cfg(feature = "discover-bottles")]
+mod bottles;
+#[cfg(feature = "discover-crossover")]
+mod crossover;
+#[cfg(feature = "discover-lutris")]
+mod lutris;
+#[cfg(feature = "discover-playonlinux")]
+mod playonlinux;
+
+#[cfg(feature = "discover-bottles")]
+pub use bottles::{bottles, Bottle};
+#[cfg(feature = "discover-crossover")]
+pub use crossover::{crossover_bottles, CrossOverBottle};
+#[cfg(feature = "discover-lutris")]
+pub use lutris::{lutris_games, LutrisGame};
+#[cfg(feature = "discover-playonlinux")]
+pub use playonlinux::{playonlinux_prefixes, PlayOnLinuxPrefix};
+
+/// Get the home directory of the current user.
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+/// Get an XDG base directory, like `XDG_CONFIG_HOME`, or its default in the home directory.
+#[cfg(any(feature = "discover-bottles", feature = "discover-lutris"))]
+fn xdg_dir(var: &str, default: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(default)))
+}
+
+/// List the subdirectories of a directory, sorted by name.
+#[cfg(any(
+    feature = "discover-bottles",
+    feature = "discover-crossover",
+    feature = "discover-playonlinux"
+))]
+fn subdirectories(dir: &crate::NativePath) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = match dir.read_dir() {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect(),
+        Err(_) => vec![],
+    };
+    dirs.sort();
+    dirs
+}
+
+/// Read a scalar value from a simple YAML file.
+///
+/// Only `key: value` lines are supported. If `section` is given, the key is looked up in the
+/// indented block under `section:`, otherwise at the top level.
+pub(crate) fn yaml_value(contents: &str, section: Option<&str>, key: &str) -> Option<String> {
+    let mut current_section = None;
+    for line in contents.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let is_indented = trimmed.len() < line.len();
+        if !is_indented {
+            current_section = trimmed.strip_suffix(':');
+        }
+        let in_section = match section {
+            Some(section) => is_indented && current_section == Some(section),
+            None => !is_indented,
+        };
+        if !in_section {
+            continue;
+        }
+        if let Some(value) = trimmed
+            .strip_prefix(key)
+            .and_then(|rest| rest.strip_prefix(':'))
+        {
+            let value = value.trim();
+            let unquoted = value
+                .strip_prefix('\'')
+                .and_then(|value| value.strip_suffix('\''))
+                .or_else(|| {
+                    value
+                        .strip_prefix('"')
+                        .and_then(|value| value.strip_suffix('"'))
+                })
+                .unwrap_or(value);
+            return Some(unquoted.to_string()).filter(|value| !value.is_empty());
+        }
+    }
+    None
+}
+
+/// The kind of a discovered wine prefix: which program manages it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrefixKind {
+    /// A prefix that is not managed by a frontend, like `~/.wine`.
+    Plain,
+    /// The Proton prefix of a Steam game.
+    Proton,
+    /// The prefix of a Lutris game.
+    Lutris,
+    /// A bottle managed by Bottles.
+    Bottles,
+    /// A virtual drive managed by PlayOnLinux, PlayOnMac or Phoenicis.
+    PlayOnLinux,
+    /// A bottle managed by CrossOver.
+    CrossOver,
+}
+
+/// A wine prefix found by [`discover_prefixes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredPrefix {
+    /// The path to the wine prefix.
+    pub path: PathBuf,
+    /// The program that manages the prefix.
+    pub kind: PrefixKind,
+    /// The name of the prefix in its frontend, like the name of a game or a bottle.
+    pub name: Option<String>,
+    /// The architecture of the prefix. If the frontend doesn't know it, it is read from the
+    /// registry of the prefix.
+    pub arch: Option<WineArch>,
+}
+
+/// Find the plain wine prefixes: `WINEPREFIX`, `~/.wine`, and the prefix of the
+/// `org.winehq.Wine` Flatpak.
+fn plain_prefixes() -> Vec<DiscoveredPrefix> {
+    let mut paths = vec![];
+    paths.extend(std::env::var_os("WINEPREFIX").map(PathBuf::from));
+    if let Some(home) = home_dir() {
+        paths.push(home.join(".wine"));
+        paths.push(home.join(crate::FLATPAK_PREFIX));
+    }
+    paths
+        .into_iter()
+        .filter(|path| path.join("system.reg").is_file() || path.join("dosdevices").is_dir())
+        .map(|path| DiscoveredPrefix {
+            path,
+            kind: PrefixKind::Plain,
+            name: None,
+            arch: None,
+        })
+        .collect()
+}
+
+/// Find the wine prefixes managed by all supported frontends, and the plain prefixes like
+/// `~/.wine`.
+///
+/// Plain prefixes come first, then the prefixes of each frontend. A prefix that is managed by a
+/// frontend is only listed once, with the kind of that frontend. Prefixes that don't exist are
+/// skipped.
+///
+/// ```rust,no_run
+/// use winepath::{discover::discover_prefixes, WineConfig};
+/// for prefix in discover_prefixes() {
+///     let config = WineConfig::from_prefix(&prefix.path);
+///     println!("{:?} {:?}: {}", prefix.kind, prefix.name, config.prefix().display());
+/// }
+/// ```
+pub fn discover_prefixes() -> Vec<DiscoveredPrefix> {
+    // Not mutated if all frontends are disabled.
+    #[allow(unused_mut)]
+    let mut managed: Vec<DiscoveredPrefix> = vec![];
+    #[cfg(feature = "discover-steam")]
+    if let Some(home) = home_dir() {
+        managed.extend(
+            crate::steam::proton_prefixes(&home)
+                .into_iter()
+                .map(|prefix| DiscoveredPrefix {
+                    path: prefix.compat_data.join("pfx"),
+                    kind: PrefixKind::Proton,
+                    name: prefix.name.or(Some(prefix.app_id)),
+                    arch: None,
+                }),
+        );
+    }
+    #[cfg(feature = "discover-lutris")]
+    managed.extend(lutris_games().into_iter().map(|game| DiscoveredPrefix {
+        path: game.prefix,
+        kind: PrefixKind::Lutris,
+        name: game.name.or(Some(game.slug)),
+        arch: game.arch,
+    }));
+    #[cfg(feature = "discover-bottles")]
+    managed.extend(bottles().into_iter().map(|bottle| DiscoveredPrefix {
+        path: bottle.prefix,
+        kind: PrefixKind::Bottles,
+        name: Some(bottle.name),
+        arch: bottle.arch,
+    }));
+    #[cfg(feature = "discover-playonlinux")]
+    managed.extend(
+        playonlinux_prefixes()
+            .into_iter()
+            .map(|prefix| DiscoveredPrefix {
+                path: prefix.prefix,
+                kind: PrefixKind::PlayOnLinux,
+                name: Some(prefix.name),
+                arch: prefix.arch,
+            }),
+    );
+    #[cfg(feature = "discover-crossover")]
+    managed.extend(
+        crossover_bottles()
+            .into_iter()
+            .map(|bottle| DiscoveredPrefix {
+                path: bottle.prefix,
+                kind: PrefixKind::CrossOver,
+                name: Some(bottle.name),
+                arch: bottle.arch,
+            }),
+    );
+
+    let mut prefixes: Vec<DiscoveredPrefix> = plain_prefixes()
+        .into_iter()
+        .filter(|plain| !managed.iter().any(|prefix| prefix.path == plain.path))
+        .collect();
+    for prefix in managed {
+        // Several Lutris games can share a prefix, and a configured prefix may not exist yet.
+        if prefix.path.is_dir() && !prefixes.iter().any(|seen| seen.path == prefix.path) {
+            prefixes.push(prefix);
+        }
+    }
+    for prefix in &mut prefixes {
+        if prefix.arch.is_none() {
+            prefix.arch = RegistryFile::read(&prefix.path, "system.reg")
+                .and_then(|registry| WineArch::from_registry(&registry));
+        }
+    }
+    prefixes
+}
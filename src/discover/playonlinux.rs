@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use super::{home_dir, subdirectories};
+use crate::WineArch;
+
+/// A virtual drive managed by PlayOnLinux, PlayOnMac or Phoenicis. See
+/// [`playonlinux_prefixes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayOnLinuxPrefix {
+    /// The name of the virtual drive.
+    pub name: String,
+    /// The architecture of the virtual drive, if it is configured.
+    pub arch: Option<WineArch>,
+    /// The Wine version of the virtual drive, like `4.0`.
+    pub wine_version: Option<String>,
+    /// The wine prefix of the virtual drive.
+    pub prefix: PathBuf,
+}
+
+/// Parse a PlayOnLinux architecture name.
+fn playonlinux_arch(arch: &str) -> Option<WineArch> {
+    match arch {
+        "x86" => Some(WineArch::Win32),
+        "amd64" | "x86_64" => Some(WineArch::Win64),
+        _ => None,
+    }
+}
+
+/// Read a string value from the `phoenicis.cfg` JSON file of a Phoenicis container.
+fn json_string_value(contents: &str, key: &str) -> Option<String> {
+    let quoted_key = format!("\"{}\"", key);
+    let rest = contents[contents.find(&quoted_key)? + quoted_key.len()..].trim_start();
+    let rest = rest.strip_prefix(':')?.trim_start().strip_prefix('"')?;
+    Some(rest[..rest.find('"')?].to_string())
+}
+
+/// Read the configuration of a PlayOnLinux virtual drive, from `playonlinux.cfg` or
+/// `phoenicis.cfg`. Returns `None` if it is not a wine prefix.
+fn read_playonlinux_prefix(prefix: PathBuf) -> Option<PlayOnLinuxPrefix> {
+    let name = prefix.file_name()?.to_str()?.to_string();
+    let (arch, wine_version) =
+        if let Ok(config) = std::fs::read_to_string(prefix.join("playonlinux.cfg")) {
+            // `KEY=value` lines.
+            let value = |key: &str| {
+                config.lines().find_map(|line| {
+                    let value = line.strip_prefix(key)?.strip_prefix('=')?.trim();
+                    Some(value.to_string()).filter(|value| !value.is_empty())
+                })
+            };
+            (value("ARCH"), value("VERSION"))
+        } else if let Ok(config) = std::fs::read_to_string(prefix.join("phoenicis.cfg")) {
+            (
+                json_string_value(&config, "wineArchitecture"),
+                json_string_value(&config, "wineVersion"),
+            )
+        } else if prefix.join("system.reg").is_file() {
+            (None, None)
+        } else {
+            return None;
+        };
+    Some(PlayOnLinuxPrefix {
+        name,
+        arch: arch.and_then(|arch| playonlinux_arch(&arch)),
+        wine_version,
+        prefix,
+    })
+}
+
+/// Find the virtual drives managed by PlayOnLinux, PlayOnMac, or its successor Phoenicis.
+///
+/// ```rust,no_run
+/// use winepath::{discover::playonlinux_prefixes, WineConfig};
+/// for virtual_drive in playonlinux_prefixes() {
+///     let config = WineConfig::from_prefix(&virtual_drive.prefix);
+///     println!("{}: {}", virtual_drive.name, config.prefix().display());
+/// }
+/// ```
+pub fn playonlinux_prefixes() -> Vec<PlayOnLinuxPrefix> {
+    let home = match home_dir() {
+        Some(home) => home,
+        None => return vec![],
+    };
+    let dirs = [
+        home.join(".PlayOnLinux/wineprefix"),
+        home.join("Library/PlayOnMac/wineprefix"),
+        home.join(".Phoenicis/containers/wineprefix"),
+    ];
+    dirs.iter()
+        .flat_map(|dir| subdirectories(dir))
+        .filter_map(read_playonlinux_prefix)
+        .collect()
+}
@@ -0,0 +1,156 @@
+//! A file that caches the drive mappings of a prefix between runs.
+//!
+//! The file starts with a key that describes the state of the prefix when it was written: the
+//! prefix path, and the modification times of the files that the drive mappings are read from.
+//! The drive mappings follow, one per line, with tab-separated fields.
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::{DriveMap, NativePath, WineConfig};
+
+/// The first line of a cache file. Bump the version when the format changes.
+const MAGIC: &[u8] = b"winepath drive cache 2\n";
+
+/// Append a field to a line, escaping tabs, newlines and backslashes.
+pub(crate) fn push_field(line: &mut Vec<u8>, field: &[u8]) {
+    line.push(b'\t');
+    for &byte in field {
+        match byte {
+            b'\\' => line.extend_from_slice(b"\\\\"),
+            b'\t' => line.extend_from_slice(b"\\t"),
+            b'\n' => line.extend_from_slice(b"\\n"),
+            byte => line.push(byte),
+        }
+    }
+}
+
+/// Undo the escaping of [`push_field`]. Returns `None` for invalid escapes.
+pub(crate) fn parse_field(field: &[u8]) -> Option<Vec<u8>> {
+    let mut bytes = field.iter();
+    let mut parsed = Vec::with_capacity(field.len());
+    while let Some(&byte) = bytes.next() {
+        parsed.push(match byte {
+            b'\\' => match bytes.next()? {
+                b'\\' => b'\\',
+                b't' => b'\t',
+                b'n' => b'\n',
+                _ => return None,
+            },
+            byte => byte,
+        });
+    }
+    Some(parsed)
+}
+
+/// Parse a field as a path, or `None` if it is empty.
+pub(crate) fn parse_path(field: &[u8]) -> Option<Option<PathBuf>> {
+    let field = parse_field(field)?;
+    Some(if field.is_empty() {
+        None
+    } else {
+        Some(PathBuf::from(OsStr::from_bytes(&field)))
+    })
+}
+
+/// Append a line with the modification time of a file, or an empty field if it does not exist.
+fn push_mtime(key: &mut Vec<u8>, name: &str, path: &NativePath) {
+    key.extend_from_slice(b"mtime");
+    push_field(key, name.as_bytes());
+    let mtime = path
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+        .map(|mtime| format!("{}.{:09}", mtime.as_secs(), mtime.subsec_nanos()))
+        .unwrap_or_default();
+    push_field(key, mtime.as_bytes());
+    key.push(b'\n');
+}
+
+impl WineConfig {
+    /// Build the key of the cache file: the lines that must match for the cached mappings to be
+    /// up to date.
+    fn drive_cache_key(&self) -> Vec<u8> {
+        let prefix = self.prefix();
+        let mut key = MAGIC.to_vec();
+        key.extend_from_slice(b"prefix");
+        push_field(&mut key, prefix.as_os_str().as_bytes());
+        key.push(b'\n');
+        key.extend_from_slice(b"defaults");
+        push_field(&mut key, if self.default_mappings { b"1" } else { b"0" });
+        key.push(b'\n');
+        let dosdevices = prefix.join("dosdevices");
+        push_mtime(&mut key, "dosdevices", &dosdevices);
+        push_mtime(&mut key, "dosdevices/unc", &dosdevices.join("unc"));
+        push_mtime(&mut key, "system.reg", &prefix.join("system.reg"));
+        key
+    }
+
+    /// Use the drive mappings from a cache file written by [`WineConfig::save_drive_cache`], if
+    /// it is up to date. Returns `true` if the cached mappings are used.
+    ///
+    /// The cache is up to date if it was written for the same prefix, and the `dosdevices`
+    /// directory, its `unc` directory and the `system.reg` file have not been modified since.
+    /// Drive symlinks are always replaced rather than changed in place, which modifies
+    /// `dosdevices`. Changes to the targets of the drives, like mounting a disk on an existing
+    /// mount point, are not noticed.
+    ///
+    /// Use this in short-lived programs, to skip scanning the prefix:
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let cache_file = "/home/username/.cache/my-tool/drives";
+    /// if !config.load_drive_cache(cache_file) {
+    ///     config.save_drive_cache(cache_file).unwrap();
+    /// }
+    /// ```
+    pub fn load_drive_cache(&self, cache_file: impl AsRef<Path>) -> bool {
+        let _span = debug_span!("load_drive_cache", cache_file = %cache_file.as_ref().display());
+        let data = match fs::read(cache_file) {
+            Ok(data) => data,
+            Err(_err) => {
+                debug_event!(error = %_err, "could not read the drive cache");
+                return false;
+            }
+        };
+        let key = self.drive_cache_key();
+        let drive_map = data
+            .strip_prefix(key.as_slice())
+            .and_then(DriveMap::read_cache);
+        match drive_map {
+            Some(drive_map) => {
+                debug_event!("using the cached drives");
+                self.set_drive_map(drive_map);
+                true
+            }
+            None => {
+                debug_event!("the drive cache is stale");
+                false
+            }
+        }
+    }
+
+    /// Write the drive mappings to a cache file, to be used by [`WineConfig::load_drive_cache`]
+    /// later. The prefix is scanned first if that has not happened yet.
+    ///
+    /// The file is replaced atomically, so concurrent readers never see a partial cache. The
+    /// parent directory must exist.
+    pub fn save_drive_cache(&self, cache_file: impl AsRef<Path>) -> io::Result<()> {
+        let cache_file = cache_file.as_ref();
+        // Compute the key before the mappings, so changes in between make the cache stale.
+        let mut data = self.drive_cache_key();
+        self.drive_map().write_cache(&mut data);
+        let mut temp_name = cache_file.as_os_str().to_os_string();
+        temp_name.push(format!(".{}.tmp", std::process::id()));
+        let temp_file = PathBuf::from(temp_name);
+        fs::write(&temp_file, data)?;
+        fs::rename(&temp_file, cache_file).inspect_err(|_| {
+            let _ = fs::remove_file(&temp_file);
+        })
+    }
+}
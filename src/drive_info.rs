@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+
+use crate::registry::RegistryFile;
+use crate::NativePath;
+
+/// Name of the file in a drive root that holds the volume label.
+pub(crate) const LABEL_FILE: &str = ".windows-label";
+/// Name of the file in a drive root that holds the volume serial number.
+pub(crate) const SERIAL_FILE: &str = ".windows-serial";
+
+/// Information about a mapped drive, like `winecfg` shows it. See [`WineConfig::drive_info`].
+///
+/// [`WineConfig::drive_info`]: crate::WineConfig::drive_info
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriveInfo {
+    /// The drive letter, in lowercase.
+    pub letter: char,
+    /// The native path that the drive is mapped to.
+    pub target: PathBuf,
+    /// The kind of drive.
+    pub drive_type: DriveType,
+    /// The volume label, from the `.windows-label` file in the drive root.
+    pub label: Option<String>,
+    /// The volume serial number, from the `.windows-serial` file in the drive root.
+    pub serial: Option<u32>,
+}
+
+/// Read the volume label from a drive root, like Wine.
+pub(crate) fn read_label(root: &NativePath) -> Option<String> {
+    let label = std::fs::read_to_string(root.join(LABEL_FILE)).ok()?;
+    Some(label.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Read the volume serial number from a drive root, like Wine. It is stored in hexadecimal.
+pub(crate) fn read_serial(root: &NativePath) -> Option<u32> {
+    let serial = std::fs::read_to_string(root.join(SERIAL_FILE)).ok()?;
+    u32::from_str_radix(serial.trim(), 16).ok()
+}
+
+/// Write the volume label to a drive root, like Wine.
+pub(crate) fn write_label(root: &NativePath, label: &str) -> std::io::Result<()> {
+    std::fs::write(root.join(LABEL_FILE), format!("{}\n", label))
+}
+
+/// Write the volume serial number to a drive root, like Wine.
+pub(crate) fn write_serial(root: &NativePath, serial: u32) -> std::io::Result<()> {
+    std::fs::write(root.join(SERIAL_FILE), format!("{:08x}\n", serial))
+}
+
+/// The kind of a drive, like `GetDriveType` returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DriveType {
+    /// A hard disk. This is the default.
+    #[default]
+    Fixed,
+    /// A CD-ROM or DVD drive.
+    CdRom,
+    /// A network share.
+    Network,
+    /// A floppy disk drive.
+    Floppy,
+}
+
+impl DriveType {
+    /// Parse a drive type from the `Software\Wine\Drives` registry key.
+    pub(crate) fn from_registry(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "hd" => Some(DriveType::Fixed),
+            "cdrom" => Some(DriveType::CdRom),
+            "network" => Some(DriveType::Network),
+            "floppy" => Some(DriveType::Floppy),
+            _ => None,
+        }
+    }
+
+    /// Get the name of the drive type in the `Software\Wine\Drives` registry key.
+    #[cfg(unix)]
+    pub(crate) fn registry_name(self) -> &'static str {
+        match self {
+            DriveType::Fixed => "hd",
+            DriveType::CdRom => "cdrom",
+            DriveType::Network => "network",
+            DriveType::Floppy => "floppy",
+        }
+    }
+
+    /// Guess a drive type from the device that the `dosdevices/x::` symlink points to.
+    fn from_device(device: &NativePath) -> Option<Self> {
+        let name = device.file_name()?.to_str()?;
+        let is_cdrom = ["sr", "scd", "cdrom", "dvd"]
+            .iter()
+            .any(|prefix| name.starts_with(prefix));
+        if is_cdrom {
+            Some(DriveType::CdRom)
+        } else if name.starts_with("fd") {
+            Some(DriveType::Floppy)
+        } else {
+            None
+        }
+    }
+}
+
+/// Guess the type of a drive from its `dosdevices/x::` device symlink, like Wine's mount manager
+/// does for drives without a type in the registry.
+pub(crate) fn read_device_drive_type(drives_dir: &NativePath, letter: char) -> Option<DriveType> {
+    let device_link = drives_dir.join(format!("{}::", letter.to_ascii_lowercase()));
+    let device = device_link.read_link().ok()?;
+    DriveType::from_device(&device)
+}
+
+/// Find the drive types declared in the `Software\Wine\Drives` registry key. These win over the
+/// types guessed from the device symlinks.
+pub(crate) fn registry_drive_types(registry: &RegistryFile) -> Vec<(char, DriveType)> {
+    let key = match registry.key(r"Software\Wine\Drives") {
+        Some(key) => key,
+        None => return vec![],
+    };
+    key.values
+        .iter()
+        .filter_map(|(name, value)| {
+            let letter = match name.as_bytes() {
+                [letter, b':'] if letter.is_ascii_alphabetic() => char::from(*letter),
+                _ => return None,
+            };
+            let drive_type = DriveType::from_registry(value.as_str()?)?;
+            Some((letter, drive_type))
+        })
+        .collect()
+}
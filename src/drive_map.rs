@@ -0,0 +1,563 @@
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+use std::{
+    cmp::Reverse,
+    fmt::{self, Debug, Display, Formatter},
+    iter::FromIterator,
+    path::{Path, PathBuf},
+};
+
+#[cfg(unix)]
+use crate::drive_cache::{parse_field, parse_path, push_field};
+use crate::drive_info::{read_device_drive_type, registry_drive_types, DriveType};
+use crate::registry::{RegistryFile, RegistryValue};
+use crate::wine_path::{str_eq_ignore_case, strip_prefix_ignore_ascii_case};
+use crate::{drive_to_index, index_to_drive, make_absolute, normalize_lexically, NativePath};
+
+/// A `\\server\share` mapping from the `dosdevices/unc` directory.
+#[derive(Debug, Clone)]
+struct UncShare {
+    server: String,
+    share: String,
+    path: PathBuf,
+    /// The uncanonicalized symlink target, if it differs from `path`.
+    link: Option<PathBuf>,
+}
+
+/// The root of a wine path: either a drive letter or a UNC share.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WineRoot<'a> {
+    Drive(char),
+    Unc(&'a str, &'a str),
+}
+
+impl Display for WineRoot<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WineRoot::Drive(letter) => write!(f, "{}:", letter),
+            WineRoot::Unc(server, share) => write!(f, r"\\{}\{}", server, share),
+        }
+    }
+}
+
+/// Get the bytes of an optional path for a drive cache file, or nothing if it is `None`.
+#[cfg(unix)]
+fn path_bytes(path: &Option<PathBuf>) -> &[u8] {
+    path.as_ref()
+        .map_or(&[], |path| path.as_os_str().as_bytes())
+}
+
+/// A root in the depth-sorted table of a [`DriveMap`].
+#[derive(Debug, Clone, Copy)]
+enum RootSlot {
+    /// The canonical target of a drive.
+    Drive(usize),
+    /// The symlink target of a drive.
+    DriveLink(usize),
+    /// The canonical target of a UNC share.
+    Share(usize),
+    /// The symlink target of a UNC share.
+    ShareLink(usize),
+}
+
+/// Read the target of a symlink, and make it absolute without resolving any further symlinks.
+fn link_target(link: &NativePath) -> Option<PathBuf> {
+    let target = link.read_link().ok()?;
+    let parent = link.parent().unwrap_or(link);
+    Some(normalize_lexically(&make_absolute(&parent.join(target))))
+}
+
+/// The drive letter → native path mappings of a wine prefix, and its UNC shares.
+///
+/// [`WineConfig`][crate::WineConfig] reads this from the `dosdevices` directory of the prefix.
+/// A map can also be built by hand:
+///
+/// ```rust
+/// use winepath::DriveMap;
+/// use std::path::{Path, PathBuf};
+/// let drives: DriveMap = vec![
+///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+///     ('z', PathBuf::from("/")),
+/// ].into_iter().collect();
+/// assert!(drives.contains('C'));
+/// assert_eq!(drives.get('z'), Some(Path::new("/")));
+/// assert_eq!(drives.len(), 2);
+/// ```
+#[derive(Clone, Default)]
+pub struct DriveMap {
+    drives: [Option<PathBuf>; 26],
+    /// The uncanonicalized symlink targets of the drives, if they differ from `drives`.
+    links: [Option<PathBuf>; 26],
+    /// Drives whose symlink target does not exist. These map to the lexical target.
+    offline: [bool; 26],
+    /// The types of the drives, from the registry or the `dosdevices/x::` device symlinks.
+    drive_types: [Option<DriveType>; 26],
+    unc_shares: Vec<UncShare>,
+    /// All roots, deepest first. Rebuilt by [`DriveMap::sort_roots`] after every change.
+    sorted: Vec<RootSlot>,
+}
+
+impl DriveMap {
+    /// Create an empty drive map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the drive mappings from the `dosdevices` directory of a wine prefix.
+    ///
+    /// Drives that are real directories in `dosdevices` map to that directory. Drives without an
+    /// entry are read from the registry in `system.reg` if they are declared there, like in some
+    /// older prefixes. Drive targets are canonicalized. Drives whose target does not exist, like
+    /// an unmounted disk, map to the symlink target resolved against `dosdevices`, and are
+    /// [offline][DriveMap::is_offline].
+    pub fn from_prefix(prefix: impl AsRef<NativePath>) -> Self {
+        let _span = debug_span!("scan_drives", prefix = %prefix.as_ref().display());
+        let drives_dir = prefix.as_ref().join("dosdevices");
+        let mut drive_map = Self::default();
+
+        for letter in b'a'..=b'z' {
+            drive_map.read_drive(&drives_dir, char::from(letter));
+        }
+        drive_map.fill_from_registry(prefix.as_ref(), None);
+        drive_map.unc_shares = scan_unc_shares(&drives_dir.join("unc"));
+        drive_map.sort_roots();
+        debug_event!(drives = ?drive_map, "scanned drives");
+        drive_map
+    }
+
+    /// Map drives that are not mapped yet to the targets declared in the registry of a prefix.
+    /// If `only` is set, only that drive is considered.
+    pub(crate) fn fill_from_registry(&mut self, prefix: &NativePath, only: Option<char>) {
+        let registry = match RegistryFile::read(prefix, "system.reg") {
+            Some(registry) => registry,
+            None => return,
+        };
+        for (letter, drive_type) in registry_drive_types(&registry) {
+            let is_wanted = only.is_none_or(|only| only.eq_ignore_ascii_case(&letter));
+            if is_wanted {
+                self.drive_types[drive_to_index(letter)] = Some(drive_type);
+            }
+        }
+        for (letter, target) in registry_drives(&registry) {
+            let index = drive_to_index(letter);
+            let is_wanted = only.is_none_or(|only| only.eq_ignore_ascii_case(&letter));
+            if is_wanted && self.drives[index].is_none() {
+                debug_event!(%letter, target = %target.display(), "drive declared in the registry");
+                self.drives[index] = target.canonicalize().ok();
+            }
+        }
+        self.sort_roots();
+    }
+
+    /// Map `c:` to `drive_c` in a prefix and `z:` to `/` if they are not mapped yet, like
+    /// `wineboot` does when it creates the `dosdevices` directory. If `only` is set, only that
+    /// drive is considered.
+    pub(crate) fn fill_defaults(&mut self, prefix: &NativePath, only: Option<char>) {
+        let drive_c = prefix.join("drive_c");
+        let drive_c = drive_c
+            .canonicalize()
+            .unwrap_or_else(|_| normalize_lexically(&make_absolute(&drive_c)));
+        for (letter, target) in [('c', drive_c), ('z', PathBuf::from("/"))] {
+            let index = drive_to_index(letter);
+            let is_wanted = only.is_none_or(|only| only.eq_ignore_ascii_case(&letter));
+            if is_wanted && self.drives[index].is_none() {
+                self.drives[index] = Some(target);
+            }
+        }
+        self.sort_roots();
+    }
+
+    /// Read the mapping of a single drive from a `dosdevices` directory. The drive is unmapped
+    /// if it doesn't exist there.
+    ///
+    /// The entry is usually a symlink, but some tools create a real directory or a bind mount
+    /// instead: then the drive maps to the entry itself. Dangling symlinks map to their target
+    /// lexically, and the drive is marked offline.
+    pub(crate) fn read_drive(&mut self, drives_dir: &NativePath, drive_letter: char) {
+        let index = drive_to_index(drive_letter);
+        let drive_dir = drives_dir.join(format!("{}:", index_to_drive(index)));
+        let is_directory = drive_dir
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.is_dir());
+        let (resolved_path, link) = if is_directory {
+            let resolved_path = drive_dir.canonicalize().ok();
+            (
+                resolved_path,
+                Some(normalize_lexically(&make_absolute(&drive_dir))),
+            )
+        } else {
+            let resolved_path = drive_dir
+                .read_link()
+                .and_then(|target| drives_dir.join(target).canonicalize())
+                .ok();
+            (resolved_path, link_target(&drive_dir))
+        };
+        self.drive_types[index] = read_device_drive_type(drives_dir, drive_letter);
+        self.offline[index] = resolved_path.is_none() && link.is_some() && !is_directory;
+        if self.offline[index] {
+            debug_event!(drive = %drive_letter, ?link, "drive target does not exist");
+            self.links[index] = None;
+            self.drives[index] = link;
+        } else {
+            self.links[index] = resolved_path
+                .as_ref()
+                .and_then(|resolved_path| link.filter(|link| link != resolved_path));
+            self.drives[index] = resolved_path;
+        }
+        self.sort_roots();
+    }
+
+    /// Iterate over the mapped drives, in alphabetical order.
+    pub fn iter(&self) -> impl Iterator<Item = (char, &Path)> {
+        self.drives.iter().enumerate().filter_map(|(index, path)| {
+            path.as_ref()
+                .map(|path| (index_to_drive(index), path.as_ref()))
+        })
+    }
+
+    /// Get the native path that a drive is mapped to.
+    pub fn get(&self, drive_letter: char) -> Option<&Path> {
+        if !drive_letter.is_ascii_alphabetic() {
+            return None;
+        }
+        self.drives
+            .get(drive_to_index(drive_letter))
+            .and_then(|path| path.as_ref().map(|path| path.as_ref()))
+    }
+
+    /// Check if a drive is mapped.
+    pub fn contains(&self, drive_letter: char) -> bool {
+        self.get(drive_letter).is_some()
+    }
+
+    /// Check if a drive is mapped, but its target does not exist, e.g. because a disk is not
+    /// mounted. Paths on the drive are still converted.
+    pub fn is_offline(&self, drive_letter: char) -> bool {
+        drive_letter.is_ascii_alphabetic() && self.offline[drive_to_index(drive_letter)]
+    }
+
+    /// Get the type of a drive, as detected when the drive mappings were read.
+    pub(crate) fn drive_type(&self, drive_letter: char) -> DriveType {
+        if !drive_letter.is_ascii_alphabetic() {
+            return DriveType::default();
+        }
+        self.drive_types[drive_to_index(drive_letter)].unwrap_or_default()
+    }
+
+    /// Get the number of mapped drives.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Check if no drives are mapped.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Map a drive to a native path, returning the previous mapping.
+    ///
+    /// The path should be canonical, or native paths won't match it when converting them.
+    /// Panics if `drive_letter` is not an ASCII letter.
+    pub fn insert(&mut self, drive_letter: char, path: impl Into<PathBuf>) -> Option<PathBuf> {
+        let index = drive_to_index(drive_letter);
+        self.links[index] = None;
+        self.offline[index] = false;
+        self.drive_types[index] = None;
+        let previous = self.drives[index].replace(path.into());
+        self.sort_roots();
+        previous
+    }
+
+    /// Unmap a drive, returning its mapping.
+    pub fn remove(&mut self, drive_letter: char) -> Option<PathBuf> {
+        if !drive_letter.is_ascii_alphabetic() {
+            return None;
+        }
+        let index = drive_to_index(drive_letter);
+        self.links[index] = None;
+        self.offline[index] = false;
+        self.drive_types[index] = None;
+        let previous = self.drives[index].take();
+        self.sort_roots();
+        previous
+    }
+
+    /// Iterate over the UNC shares, as `(server, share, path)`.
+    pub fn unc_shares(&self) -> impl Iterator<Item = (&str, &str, &Path)> {
+        self.unc_shares
+            .iter()
+            .map(|unc| (unc.server.as_str(), unc.share.as_str(), unc.path.as_ref()))
+    }
+
+    /// Get the native path that a `\\server\share` UNC share is mapped to.
+    ///
+    /// Server and share names are compared case-insensitively.
+    pub fn get_unc(&self, server: &str, share: &str) -> Option<&Path> {
+        self.unc_shares
+            .iter()
+            .find(|unc| {
+                str_eq_ignore_case(&unc.server, server) && str_eq_ignore_case(&unc.share, share)
+            })
+            .map(|unc| unc.path.as_ref())
+    }
+
+    /// Iterate over all drive and UNC share roots.
+    ///
+    /// Roots whose symlink target is not canonical are yielded twice: once with the canonical
+    /// path, and once with the symlink target, so native paths match in either form.
+    pub(crate) fn roots(&self) -> impl Iterator<Item = (WineRoot<'_>, &Path)> {
+        let drives = self
+            .iter()
+            .map(|(letter, path)| (WineRoot::Drive(letter), path));
+        let drive_links = self.links.iter().enumerate().filter_map(|(index, link)| {
+            link.as_ref()
+                .map(|link| (WineRoot::Drive(index_to_drive(index)), link.as_ref()))
+        });
+        let shares = self.unc_shares.iter().flat_map(|unc| {
+            let root = WineRoot::Unc(&unc.server, &unc.share);
+            std::iter::once((root, unc.path.as_ref()))
+                .chain(unc.link.as_ref().map(|link| (root, link.as_ref())))
+        });
+        drives.chain(drive_links).chain(shares)
+    }
+
+    /// Iterate over all drive and UNC share roots like [`DriveMap::roots`], but deepest first.
+    ///
+    /// Roots at the same depth are in the same order as in [`DriveMap::roots`]. The first root
+    /// that a native path is in is the most specific one.
+    pub(crate) fn roots_by_depth(&self) -> impl Iterator<Item = (WineRoot<'_>, &Path)> {
+        self.sorted
+            .iter()
+            .filter_map(move |slot| self.resolve_slot(*slot))
+    }
+
+    fn resolve_slot(&self, slot: RootSlot) -> Option<(WineRoot<'_>, &Path)> {
+        let (root, path) = match slot {
+            RootSlot::Drive(index) => (
+                WineRoot::Drive(index_to_drive(index)),
+                self.drives[index].as_ref()?,
+            ),
+            RootSlot::DriveLink(index) => (
+                WineRoot::Drive(index_to_drive(index)),
+                self.links[index].as_ref()?,
+            ),
+            RootSlot::Share(index) => {
+                let unc = &self.unc_shares[index];
+                (WineRoot::Unc(&unc.server, &unc.share), &unc.path)
+            }
+            RootSlot::ShareLink(index) => {
+                let unc = &self.unc_shares[index];
+                (WineRoot::Unc(&unc.server, &unc.share), unc.link.as_ref()?)
+            }
+        };
+        Some((root, path.as_ref()))
+    }
+
+    /// Rebuild the depth-sorted table of roots.
+    fn sort_roots(&mut self) {
+        let drives = (0..26).filter(|&index| self.drives[index].is_some());
+        let drive_links = (0..26).filter(|&index| self.links[index].is_some());
+        let shares = self.unc_shares.iter().enumerate().flat_map(|(index, unc)| {
+            std::iter::once(RootSlot::Share(index))
+                .chain(unc.link.as_ref().map(|_| RootSlot::ShareLink(index)))
+        });
+        let mut sorted: Vec<RootSlot> = drives
+            .map(RootSlot::Drive)
+            .chain(drive_links.map(RootSlot::DriveLink))
+            .chain(shares)
+            .collect();
+        // The sort is stable, so roots at the same depth keep their order.
+        sorted.sort_by_cached_key(|slot| {
+            Reverse(
+                self.resolve_slot(*slot)
+                    .map_or(0, |(_, path)| path.components().count()),
+            )
+        });
+        self.sorted = sorted;
+    }
+
+    /// Append the mappings to a drive cache file, one per line.
+    #[cfg(unix)]
+    pub(crate) fn write_cache(&self, data: &mut Vec<u8>) {
+        for index in 0..26 {
+            if self.drives[index].is_none() {
+                continue;
+            }
+            data.extend_from_slice(b"drive");
+            push_field(data, &[index_to_drive(index) as u8]);
+            push_field(data, path_bytes(&self.drives[index]));
+            push_field(data, path_bytes(&self.links[index]));
+            push_field(data, if self.offline[index] { b"1" } else { b"0" });
+            let drive_type = self.drive_types[index].map_or("", DriveType::registry_name);
+            push_field(data, drive_type.as_bytes());
+            data.push(b'\n');
+        }
+        for unc in &self.unc_shares {
+            data.extend_from_slice(b"unc");
+            push_field(data, unc.server.as_bytes());
+            push_field(data, unc.share.as_bytes());
+            push_field(data, unc.path.as_os_str().as_bytes());
+            push_field(data, path_bytes(&unc.link));
+            data.push(b'\n');
+        }
+    }
+
+    /// Read the mappings written by [`DriveMap::write_cache`]. Returns `None` if the data is
+    /// malformed.
+    #[cfg(unix)]
+    pub(crate) fn read_cache(data: &[u8]) -> Option<Self> {
+        let mut drive_map = Self::default();
+        for line in data.split(|&byte| byte == b'\n') {
+            let mut fields = line.split(|&byte| byte == b'\t');
+            match (
+                fields.next()?,
+                fields.next(),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+            ) {
+                (b"", None, None, None, None, None) => {}
+                (
+                    b"drive",
+                    Some(&[letter]),
+                    Some(path),
+                    Some(link),
+                    Some(offline),
+                    Some(drive_type),
+                ) => {
+                    if !letter.is_ascii_alphabetic() {
+                        return None;
+                    }
+                    let index = drive_to_index(char::from(letter));
+                    drive_map.drives[index] = Some(parse_path(path)??);
+                    drive_map.links[index] = parse_path(link)?;
+                    drive_map.offline[index] = offline == b"1";
+                    drive_map.drive_types[index] = match drive_type {
+                        b"" => None,
+                        name => Some(DriveType::from_registry(std::str::from_utf8(name).ok()?)?),
+                    };
+                }
+                (b"unc", Some(server), Some(share), Some(path), Some(link), None) => {
+                    drive_map.unc_shares.push(UncShare {
+                        server: String::from_utf8(parse_field(server)?).ok()?,
+                        share: String::from_utf8(parse_field(share)?).ok()?,
+                        path: parse_path(path)??,
+                        link: parse_path(link)?,
+                    });
+                }
+                _ => return None,
+            }
+            if fields.next().is_some() {
+                return None;
+            }
+        }
+        drive_map.sort_roots();
+        Some(drive_map)
+    }
+}
+
+impl FromIterator<(char, PathBuf)> for DriveMap {
+    /// Build a drive map from `(drive letter, native path)` pairs. See [`DriveMap::insert`].
+    fn from_iter<I: IntoIterator<Item = (char, PathBuf)>>(iter: I) -> Self {
+        let mut drive_map = Self::new();
+        drive_map.extend(iter);
+        drive_map
+    }
+}
+
+impl Extend<(char, PathBuf)> for DriveMap {
+    fn extend<I: IntoIterator<Item = (char, PathBuf)>>(&mut self, iter: I) {
+        for (drive_letter, path) in iter {
+            self.insert(drive_letter, path);
+        }
+    }
+}
+
+/// Find the drive targets declared in the registry.
+///
+/// Wine's mount manager records the mount point of drives under `System\MountedDevices`, and
+/// drives can also be declared as paths under `Software\Wine\Drives`.
+fn registry_drives(registry: &RegistryFile) -> Vec<(char, PathBuf)> {
+    let mut drives = vec![];
+    let parse_drive = |name: &str| {
+        let bytes = name.as_bytes();
+        match bytes {
+            [letter, b':'] if letter.is_ascii_alphabetic() => Some(char::from(*letter)),
+            _ => None,
+        }
+    };
+
+    if let Some(key) = registry.key(r"System\MountedDevices") {
+        for (name, value) in &key.values {
+            let letter =
+                strip_prefix_ignore_ascii_case(name, r"\DosDevices\").and_then(parse_drive);
+            if let (Some(letter), RegistryValue::Binary(id)) = (letter, value) {
+                let id = id.split(|&byte| byte == 0).next().unwrap_or_default();
+                match std::str::from_utf8(id) {
+                    Ok(path) if path.starts_with('/') => drives.push((letter, PathBuf::from(path))),
+                    _ => {}
+                }
+            }
+        }
+    }
+    if let Some(key) = registry.key(r"Software\Wine\Drives") {
+        for (name, value) in &key.values {
+            match (parse_drive(name), value.as_str()) {
+                (Some(letter), Some(path)) if path.starts_with('/') => {
+                    drives.push((letter, PathBuf::from(path)));
+                }
+                _ => {}
+            }
+        }
+    }
+    drives
+}
+
+/// Find all `server/share` entries in a `dosdevices/unc` directory.
+fn scan_unc_shares(unc_dir: &NativePath) -> Vec<UncShare> {
+    let mut shares = vec![];
+    let servers = match unc_dir.read_dir() {
+        Ok(servers) => servers,
+        Err(_) => return shares,
+    };
+    for server in servers.filter_map(Result::ok) {
+        let server_name = match server.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        let server_shares = match server.path().read_dir() {
+            Ok(server_shares) => server_shares,
+            Err(_) => continue,
+        };
+        for share in server_shares.filter_map(Result::ok) {
+            let share_name = match share.file_name().into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            if let Ok(resolved_path) = share.path().canonicalize() {
+                let link = link_target(&share.path()).filter(|link| *link != resolved_path);
+                shares.push(UncShare {
+                    server: server_name.clone(),
+                    share: share_name,
+                    path: resolved_path,
+                    link,
+                });
+            }
+        }
+    }
+    shares
+}
+
+impl Debug for DriveMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("DriveMap");
+        for (drive_letter, path) in self.iter() {
+            s.field(std::str::from_utf8(&[drive_letter as u8]).unwrap(), &path);
+        }
+        if !self.unc_shares.is_empty() {
+            s.field("unc", &self.unc_shares);
+        }
+        s.finish()
+    }
+}
@@ -0,0 +1,149 @@
+//! Conversion of environment variables for Wine child processes.
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+use crate::{WineConfig, WinePathError};
+
+/// How [`WineConfig::to_wine_env`] converts the value of an environment variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnvVarPolicy {
+    /// Keep the value as is.
+    #[default]
+    Keep,
+    /// Convert the value if it looks like an absolute native path, or a `:`-separated list of
+    /// them. Values that can't be converted are kept as is.
+    Auto,
+    /// Convert the value as a native path. Fails if it can't be converted.
+    Path,
+    /// Convert the value as a `:`-separated native path list, like `PATH`. Fails if an entry
+    /// can't be converted. See [`WineConfig::to_wine_path_list`].
+    PathList,
+    /// Leave the variable out. The child process only goes without it if it does not inherit
+    /// the environment, see [`WineConfig::to_wine_env`].
+    Remove,
+}
+
+/// Which environment variables [`WineConfig::to_wine_env`] converts, and how.
+///
+/// Variables are matched by their exact name. Variables without a policy use the default
+/// policy, which is [`EnvVarPolicy::Keep`] unless it is changed: Wine itself reads native paths
+/// from variables like `HOME` and `WINEPREFIX`.
+///
+/// ```rust
+/// use winepath::{EnvPolicy, EnvVarPolicy};
+/// let policy = EnvPolicy::new()
+///     .var("GAME_SAVE_DIR", EnvVarPolicy::Path)
+///     .var("GAME_MOD_PATH", EnvVarPolicy::PathList)
+///     .var("LD_PRELOAD", EnvVarPolicy::Remove);
+/// assert_eq!(policy.get("GAME_SAVE_DIR"), EnvVarPolicy::Path);
+/// assert_eq!(policy.get("HOME"), EnvVarPolicy::Keep);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EnvPolicy {
+    default: EnvVarPolicy,
+    vars: HashMap<OsString, EnvVarPolicy>,
+}
+
+impl EnvPolicy {
+    /// Create a policy that keeps all variables as they are.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the policy of a variable.
+    pub fn var(mut self, name: impl Into<OsString>, policy: EnvVarPolicy) -> Self {
+        self.vars.insert(name.into(), policy);
+        self
+    }
+
+    /// Set the policy of variables that don't have their own.
+    pub fn default_policy(mut self, policy: EnvVarPolicy) -> Self {
+        self.default = policy;
+        self
+    }
+
+    /// Get the policy of a variable.
+    pub fn get(&self, name: impl AsRef<OsStr>) -> EnvVarPolicy {
+        self.vars
+            .get(name.as_ref())
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// Check if a value looks like an absolute native path or a list of them.
+fn looks_like_path_list(value: &OsStr) -> bool {
+    !value.is_empty()
+        && std::env::split_paths(value)
+            .filter(|entry| !entry.as_os_str().is_empty())
+            .all(|entry| entry.is_absolute())
+}
+
+impl WineConfig {
+    /// Convert the values of environment variables to wine paths according to `policy`, to pass
+    /// them to a Wine process.
+    ///
+    /// Takes any list of variables, like [`std::env::vars_os`], and returns the converted
+    /// variables. They are meant to be the whole environment of the child process: call
+    /// [`Command::env_clear`] before [`Command::envs`], or the child inherits the variables of the
+    /// current process, including the ones with the [`EnvVarPolicy::Remove`] policy. Returns the
+    /// error of the first variable with the [`EnvVarPolicy::Path`] or [`EnvVarPolicy::PathList`]
+    /// policy that can't be converted.
+    ///
+    /// [`Command::env_clear`]: std::process::Command::env_clear
+    /// [`Command::envs`]: std::process::Command::envs
+    ///
+    /// ```rust
+    /// use winepath::{EnvPolicy, EnvVarPolicy, WineConfig};
+    /// use std::{ffi::OsStr, path::PathBuf, process::Command};
+    /// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    ///     ('z', PathBuf::from("/")),
+    /// ]);
+    /// let policy = EnvPolicy::new()
+    ///     .var("GAME_SAVE_DIR", EnvVarPolicy::Path)
+    ///     .var("GAME_DATA", EnvVarPolicy::Auto)
+    ///     .var("LD_PRELOAD", EnvVarPolicy::Remove);
+    /// let env = config.to_wine_env(vec![
+    ///     ("GAME_SAVE_DIR", "/home/username/.wine/drive_c/saves"),
+    ///     ("GAME_DATA", "/opt/game:/opt/mods"),
+    ///     ("HOME", "/home/username"),
+    ///     ("LD_PRELOAD", "/usr/lib/libhook.so"),
+    /// ], &policy).unwrap();
+    /// assert_eq!(env[OsStr::new("GAME_SAVE_DIR")], r"c:\saves");
+    /// assert_eq!(env[OsStr::new("GAME_DATA")], r"z:\opt\game;z:\opt\mods");
+    /// assert_eq!(env[OsStr::new("HOME")], "/home/username");
+    /// assert!(!env.contains_key(OsStr::new("LD_PRELOAD")));
+    /// let mut command = Command::new("wine");
+    /// command.env_clear().envs(&env);
+    /// ```
+    pub fn to_wine_env<K, V>(
+        &self,
+        env: impl IntoIterator<Item = (K, V)>,
+        policy: &EnvPolicy,
+    ) -> Result<HashMap<OsString, OsString>, WinePathError>
+    where
+        K: Into<OsString>,
+        V: Into<OsString>,
+    {
+        let mut wine_env = HashMap::new();
+        for (name, value) in env {
+            let name = name.into();
+            let value = value.into();
+            let value = match policy.get(&name) {
+                EnvVarPolicy::Keep => value,
+                EnvVarPolicy::Remove => continue,
+                EnvVarPolicy::Path => self.to_wine_path(Path::new(&value))?.to_string().into(),
+                EnvVarPolicy::PathList => self.to_wine_path_list(&value)?.into(),
+                EnvVarPolicy::Auto if looks_like_path_list(&value) => {
+                    self.to_wine_path_list(&value).map_or(value, OsString::from)
+                }
+                EnvVarPolicy::Auto => value,
+            };
+            wine_env.insert(name, value);
+        }
+        Ok(wine_env)
+    }
+}
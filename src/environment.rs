@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use crate::registry::{RegistryFile, RegistryKey};
+use crate::wine_path::str_eq_ignore_case;
+use crate::NativePath;
+
+/// Registry key in `system.reg` with the system environment variables.
+const SYSTEM_ENVIRONMENT_KEY: &str =
+    r"System\CurrentControlSet\Control\Session Manager\Environment";
+
+/// Executable extensions to try when `PATHEXT` is not set, like `cmd.exe` does.
+pub(crate) const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/// Look up an environment variable case-insensitively, like Windows does.
+pub(crate) fn get_var<'a>(env: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    env.get(name)
+        .or_else(|| {
+            env.iter()
+                .find(|(var, _)| str_eq_ignore_case(var, name))
+                .map(|(_, value)| value)
+        })
+        .map(String::as_str)
+}
+
+/// Set an environment variable, replacing a variable with the same name in a different case.
+fn set_var(env: &mut HashMap<String, String>, name: &str, value: String) {
+    env.retain(|var, _| !str_eq_ignore_case(var, name));
+    env.insert(name.to_string(), value);
+}
+
+/// Expand `%VARIABLE%` references, like `ExpandEnvironmentStrings`. Unknown variables are kept
+/// as is.
+pub(crate) fn expand_vars(input: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('%') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(end) => match lookup(&after[..end]).filter(|_| end > 0) {
+                Some(value) => {
+                    expanded.push_str(&value);
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    // Keep the `%` and try again from the next one, which may start a variable.
+                    expanded.push('%');
+                    expanded.push_str(&after[..end]);
+                    rest = &after[end..];
+                }
+            },
+            None => {
+                expanded.push('%');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Copy all string values of a registry key into the environment.
+fn add_key(env: &mut HashMap<String, String>, key: Option<&RegistryKey>) {
+    for (name, value) in key.into_iter().flat_map(|key| &key.values) {
+        if let Some(value) = value.as_str() {
+            set_var(env, name, value.to_string());
+        }
+    }
+}
+
+/// Read the Windows environment variables of a prefix from its registry, like a new Windows
+/// process would see them.
+pub(crate) fn read_environment(prefix: &NativePath) -> HashMap<String, String> {
+    let system = RegistryFile::read(prefix, "system.reg").unwrap_or_default();
+    let user = RegistryFile::read(prefix, "user.reg").unwrap_or_default();
+    let mut env = HashMap::new();
+
+    // Variables that Wine sets itself when it starts a process.
+    let system_value = |key: &str, name: &str| {
+        system
+            .get(key, name)
+            .and_then(|value| value.as_str())
+            .map(str::to_string)
+    };
+    let system_root = system_value(
+        r"Software\Microsoft\Windows NT\CurrentVersion",
+        "SystemRoot",
+    )
+    .unwrap_or_else(|| r"C:\windows".to_string());
+    let system_drive = system_root.get(..2).unwrap_or("C:").to_string();
+    set_var(&mut env, "SystemDrive", system_drive);
+    set_var(&mut env, "windir", system_root.clone());
+    set_var(&mut env, "SystemRoot", system_root);
+    let builtin = [
+        (
+            "ProgramFiles",
+            r"Software\Microsoft\Windows\CurrentVersion",
+            "ProgramFilesDir",
+        ),
+        (
+            "ProgramFiles(x86)",
+            r"Software\Microsoft\Windows\CurrentVersion",
+            "ProgramFilesDir (x86)",
+        ),
+        (
+            "CommonProgramFiles",
+            r"Software\Microsoft\Windows\CurrentVersion",
+            "CommonFilesDir",
+        ),
+        (
+            "CommonProgramFiles(x86)",
+            r"Software\Microsoft\Windows\CurrentVersion",
+            "CommonFilesDir (x86)",
+        ),
+        (
+            "ProgramData",
+            r"Software\Microsoft\Windows NT\CurrentVersion\ProfileList",
+            "ProgramData",
+        ),
+        (
+            "ALLUSERSPROFILE",
+            r"Software\Microsoft\Windows NT\CurrentVersion\ProfileList",
+            "ProgramData",
+        ),
+        (
+            "PUBLIC",
+            r"Software\Microsoft\Windows NT\CurrentVersion\ProfileList",
+            "Public",
+        ),
+    ];
+    for (var, key, name) in builtin {
+        if let Some(value) = system_value(key, name) {
+            set_var(&mut env, var, value);
+        }
+    }
+
+    add_key(&mut env, system.key(SYSTEM_ENVIRONMENT_KEY));
+    // User variables override system variables, except for `PATH` which is appended.
+    let system_path = get_var(&env, "PATH").map(str::to_string);
+    add_key(&mut env, user.key("Environment"));
+    if let (Some(system_path), Some(user_path)) = (system_path, get_var(&env, "PATH")) {
+        if system_path != user_path {
+            let path = format!("{};{}", system_path, user_path);
+            set_var(&mut env, "PATH", path);
+        }
+    }
+    add_key(&mut env, user.key("Volatile Environment"));
+
+    // Values can refer to other variables, like `%SystemRoot%\system32`.
+    for _ in 0..4 {
+        let snapshot = env.clone();
+        for value in env.values_mut() {
+            *value = expand_vars(value, |name| get_var(&snapshot, name).map(str::to_string));
+        }
+    }
+    env
+}
@@ -0,0 +1,195 @@
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+/// Error type.
+///
+/// Most variants carry the path that could not be converted, so the error can be shown to users
+/// as is. Use [`WinePathError::kind`] to compare errors.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WinePathError {
+    /// Could not determine the wine prefix to use.
+    PrefixNotFound,
+    /// No drive letter → file path mapping is available for the native path.
+    NoDrive {
+        /// The native path.
+        path: PathBuf,
+        /// The drives and UNC shares that were considered, like `c:` or `\\server\share`, and
+        /// the native paths they are mapped to.
+        mapped: Vec<(String, PathBuf)>,
+    },
+    /// The drive of the wine path is not mapped to a native path.
+    UnmappedDrive {
+        /// The drive letter.
+        letter: char,
+    },
+    /// The wine path is malformed or has an unsupported form, e.g. it does not start with a drive
+    /// letter.
+    InvalidWinePath {
+        /// The wine path.
+        path: String,
+    },
+    /// The native path is not valid UTF-8.
+    NotUtf8 {
+        /// The native path.
+        path: PathBuf,
+    },
+    /// The native path could not be canonicalized, e.g. because it does not exist.
+    CanonicalizeFailed {
+        /// The native path.
+        path: PathBuf,
+        /// The error from the filesystem.
+        source: io::Error,
+    },
+    /// The path refers to a device in the `\\.\` namespace that is not backed by a file path,
+    /// like `\\.\COM3`.
+    UnsupportedDevice {
+        /// The device path, without the `\\.\` prefix.
+        path: String,
+    },
+    /// The native path is not inside the wine prefix.
+    OutsidePrefix {
+        /// The native path.
+        path: PathBuf,
+    },
+    /// All drive letters are in use.
+    NoFreeDrive,
+    /// A drive mapping could not be created in the `dosdevices` directory.
+    MapDriveFailed {
+        /// The native directory that the drive should map to.
+        path: PathBuf,
+        /// The error from the filesystem.
+        source: io::Error,
+    },
+    /// Reading the prefix or the environment of a process failed.
+    Io(io::Error),
+}
+
+/// The kind of a [`WinePathError`], without the details. Returned by [`WinePathError::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum WinePathErrorKind {
+    /// See [`WinePathError::PrefixNotFound`].
+    PrefixNotFound,
+    /// See [`WinePathError::NoDrive`].
+    NoDrive,
+    /// See [`WinePathError::UnmappedDrive`].
+    UnmappedDrive,
+    /// See [`WinePathError::InvalidWinePath`].
+    InvalidWinePath,
+    /// See [`WinePathError::NotUtf8`].
+    NotUtf8,
+    /// See [`WinePathError::CanonicalizeFailed`].
+    CanonicalizeFailed,
+    /// See [`WinePathError::UnsupportedDevice`].
+    UnsupportedDevice,
+    /// See [`WinePathError::OutsidePrefix`].
+    OutsidePrefix,
+    /// See [`WinePathError::NoFreeDrive`].
+    NoFreeDrive,
+    /// See [`WinePathError::MapDriveFailed`].
+    MapDriveFailed,
+    /// See [`WinePathError::Io`].
+    Io,
+}
+
+impl WinePathError {
+    /// Get the kind of the error, to compare errors or to match on them without the details.
+    ///
+    /// ```rust
+    /// use winepath::{WineConfig, WinePathErrorKind};
+    /// let config = WineConfig::from_prefix("/home/username/.wine");
+    /// let err = config.to_native_path("not a path").unwrap_err();
+    /// assert_eq!(err.kind(), WinePathErrorKind::InvalidWinePath);
+    /// ```
+    pub fn kind(&self) -> WinePathErrorKind {
+        match self {
+            WinePathError::PrefixNotFound => WinePathErrorKind::PrefixNotFound,
+            WinePathError::NoDrive { .. } => WinePathErrorKind::NoDrive,
+            WinePathError::UnmappedDrive { .. } => WinePathErrorKind::UnmappedDrive,
+            WinePathError::InvalidWinePath { .. } => WinePathErrorKind::InvalidWinePath,
+            WinePathError::NotUtf8 { .. } => WinePathErrorKind::NotUtf8,
+            WinePathError::CanonicalizeFailed { .. } => WinePathErrorKind::CanonicalizeFailed,
+            WinePathError::UnsupportedDevice { .. } => WinePathErrorKind::UnsupportedDevice,
+            WinePathError::OutsidePrefix { .. } => WinePathErrorKind::OutsidePrefix,
+            WinePathError::NoFreeDrive => WinePathErrorKind::NoFreeDrive,
+            WinePathError::MapDriveFailed { .. } => WinePathErrorKind::MapDriveFailed,
+            WinePathError::Io(_) => WinePathErrorKind::Io,
+        }
+    }
+}
+
+impl Display for WinePathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WinePathError::PrefixNotFound => write!(f, "could not determine wine prefix"),
+            WinePathError::NoDrive { path, mapped } if mapped.is_empty() => write!(
+                f,
+                "native path {} is not mapped to a wine drive (no drives are mapped)",
+                path.display()
+            ),
+            WinePathError::NoDrive { path, mapped } => {
+                write!(
+                    f,
+                    "native path {} is outside all mapped drives (",
+                    path.display()
+                )?;
+                for (index, (root, native_root)) in mapped.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} → {}", root, native_root.display())?;
+                }
+                write!(f, ")")
+            }
+            WinePathError::UnmappedDrive { letter } => {
+                write!(f, "drive {}: is not mapped", letter)
+            }
+            WinePathError::InvalidWinePath { path } => write!(f, "invalid wine path {:?}", path),
+            WinePathError::NotUtf8 { path } => {
+                write!(f, "native path {} is not valid UTF-8", path.display())
+            }
+            WinePathError::CanonicalizeFailed { path, source } => write!(
+                f,
+                "could not canonicalize native path {}: {}",
+                path.display(),
+                source
+            ),
+            WinePathError::UnsupportedDevice { path } => {
+                write!(f, r"device path \\.\{} does not refer to a file path", path)
+            }
+            WinePathError::OutsidePrefix { path } => write!(
+                f,
+                "native path {} is not inside the wine prefix",
+                path.display()
+            ),
+            WinePathError::NoFreeDrive => write!(f, "all drive letters are in use"),
+            WinePathError::MapDriveFailed { path, source } => write!(
+                f,
+                "could not create drive mapping for {}: {}",
+                path.display(),
+                source
+            ),
+            WinePathError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for WinePathError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WinePathError::CanonicalizeFailed { source, .. }
+            | WinePathError::MapDriveFailed { source, .. }
+            | WinePathError::Io(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WinePathError {
+    fn from(err: io::Error) -> Self {
+        WinePathError::Io(err)
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+
+use crate::{canonicalize, stringify_path, CanonicalizePolicy, NativePath, WineConfig};
+use crate::{WinePath, WinePathError};
+
+/// What happened to a drive or UNC share while converting a path, in an [`Explanation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CandidateOutcome {
+    /// The root maps the whole file system, and is ignored because of the
+    /// [`FallbackDrive`][crate::FallbackDrive] setting.
+    Ignored,
+    /// The path is not inside the root.
+    NotContained,
+    /// The path is inside the root, but another root was preferred according to the
+    /// [`DrivePreference`][crate::DrivePreference].
+    Outranked {
+        /// The rest of the path, relative to the root.
+        remaining: PathBuf,
+    },
+    /// The path is inside the root, and the root was used.
+    Chosen {
+        /// The rest of the path, relative to the root.
+        remaining: PathBuf,
+    },
+}
+
+/// A drive or UNC share that was considered while converting a path, in an [`Explanation`].
+///
+/// A drive whose symlink goes through other symlinks is considered twice: once with its
+/// canonical target and once with its symlink target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// The root of the wine path, like `c:` or `\\server\share`.
+    pub root: String,
+    /// The native path that the root is mapped to.
+    pub native_root: PathBuf,
+    /// Whether the root was used.
+    pub outcome: CandidateOutcome,
+}
+
+/// A trace of how a native path was converted to a wine path, from [`WineConfig::explain`].
+///
+/// The [`Display`] implementation renders the trace as a list of steps, one per line.
+#[derive(Debug)]
+pub struct Explanation {
+    /// The native path that was converted.
+    pub input: PathBuf,
+    /// The canonicalization policy that was applied to the path.
+    pub policy: CanonicalizePolicy,
+    /// The path after canonicalization, or `None` if it failed. Then `result` holds the error.
+    pub canonical: Option<PathBuf>,
+    /// The drives and UNC shares that were considered, in the order they were checked.
+    pub candidates: Vec<Candidate>,
+    /// The fallback drive, if it was used because the path is not on any drive.
+    pub fallback_drive: Option<char>,
+    /// The wine path before the [`WineFormatOptions`][crate::WineFormatOptions] were applied.
+    pub unformatted: Option<String>,
+    /// The result of the conversion, the same as [`WineConfig::to_wine_path_with`] returns.
+    pub result: Result<WinePath, WinePathError>,
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "input: {}", self.input.display())?;
+        if let Some(canonical) = &self.canonical {
+            writeln!(
+                f,
+                "canonicalized ({:?}): {}",
+                self.policy,
+                canonical.display()
+            )?;
+        }
+        for candidate in &self.candidates {
+            let native_root = candidate.native_root.display();
+            match &candidate.outcome {
+                CandidateOutcome::Ignored => writeln!(
+                    f,
+                    "  {} ({}): ignored by the fallback drive setting",
+                    candidate.root, native_root
+                )?,
+                CandidateOutcome::NotContained => writeln!(
+                    f,
+                    "  {} ({}): path is not inside the root",
+                    candidate.root, native_root
+                )?,
+                CandidateOutcome::Outranked { remaining } => writeln!(
+                    f,
+                    "  {} ({}): matches with {}, but another root is preferred",
+                    candidate.root,
+                    native_root,
+                    remaining.display()
+                )?,
+                CandidateOutcome::Chosen { remaining } => writeln!(
+                    f,
+                    "  {} ({}): chosen, with {}",
+                    candidate.root,
+                    native_root,
+                    remaining.display()
+                )?,
+            }
+        }
+        if let Some(letter) = self.fallback_drive {
+            writeln!(f, "no drive matched, using fallback drive {}:", letter)?;
+        }
+        if let Some(unformatted) = &self.unformatted {
+            writeln!(f, "stringified: {}", unformatted)?;
+        }
+        match &self.result {
+            Ok(path) => write!(f, "result: {}", path),
+            Err(err) => write!(f, "error: {}", err),
+        }
+    }
+}
+
+/// Convert a native path like [`WineConfig::to_wine_path_with`], recording every step.
+pub(crate) fn explain(
+    config: &WineConfig,
+    path: &NativePath,
+    policy: CanonicalizePolicy,
+) -> Explanation {
+    let mut explanation = Explanation {
+        input: path.to_path_buf(),
+        policy,
+        canonical: None,
+        candidates: vec![],
+        fallback_drive: None,
+        unformatted: None,
+        result: Err(WinePathError::PrefixNotFound),
+    };
+    let native = match canonicalize(path, policy) {
+        Ok(native) => native,
+        Err(err) => {
+            explanation.result = Err(err);
+            return explanation;
+        }
+    };
+    explanation.canonical = Some(native.to_path_buf());
+
+    let drive_map = config.drive_map();
+    let chosen = config
+        .matching_roots(&drive_map, &native)
+        .into_iter()
+        .next()
+        .map(|(root, remaining)| (root.to_string(), remaining.to_path_buf()));
+    let mut found_chosen = false;
+    for (root, native_root) in drive_map.roots() {
+        let is_ignored = config.is_ignored_root(root, native_root);
+        let root = root.to_string();
+        let outcome = match native.strip_prefix(native_root) {
+            _ if is_ignored => CandidateOutcome::Ignored,
+            Err(_) => CandidateOutcome::NotContained,
+            Ok(remaining) => {
+                let remaining = remaining.to_path_buf();
+                let is_chosen = chosen
+                    .as_ref()
+                    .is_some_and(|chosen| chosen.0 == root && chosen.1 == remaining);
+                if is_chosen && !found_chosen {
+                    found_chosen = true;
+                    CandidateOutcome::Chosen { remaining }
+                } else {
+                    CandidateOutcome::Outranked { remaining }
+                }
+            }
+        };
+        explanation.candidates.push(Candidate {
+            root,
+            native_root: native_root.to_path_buf(),
+            outcome,
+        });
+    }
+
+    let (root, remaining) = match config.find_drive_root(&native) {
+        Ok(found) => found,
+        Err(err) => {
+            explanation.result = Err(err);
+            return explanation;
+        }
+    };
+    if chosen.is_none() {
+        explanation.fallback_drive = root.chars().next();
+    }
+    let unformatted = match stringify_path(&root, remaining, false) {
+        Some(unformatted) => unformatted,
+        None => {
+            explanation.result = Err(WinePathError::NotUtf8 {
+                path: native.into_owned(),
+            });
+            return explanation;
+        }
+    };
+    explanation.result = Ok(WinePath(
+        config.format_options.apply(unformatted.clone(), &native),
+    ));
+    explanation.unformatted = Some(unformatted);
+    explanation
+}
@@ -0,0 +1,150 @@
+//! Extension traits, to convert paths and iterators of paths with method syntax.
+
+use std::path::PathBuf;
+
+use crate::{NativePath, PathMapper, WinePath, WinePathError};
+
+/// Convert native paths with method syntax. Implemented for [`Path`][std::path::Path], and so
+/// for [`PathBuf`] too.
+///
+/// ```rust
+/// use winepath::{WineConfig, WinePathExt};
+/// use std::path::{Path, PathBuf};
+/// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+/// ]);
+/// let path = Path::new("/home/username/.wine/drive_c/windows").to_wine(&config).unwrap();
+/// assert_eq!(path.to_string(), r"c:\windows");
+/// ```
+pub trait WinePathExt {
+    /// Convert a native path to a wine path, using a [`WineConfig`][crate::WineConfig] or another
+    /// [`PathMapper`]. See [`WineConfig::to_wine_path`][crate::WineConfig::to_wine_path].
+    fn to_wine<M: PathMapper + ?Sized>(&self, mapper: &M) -> Result<WinePath, WinePathError>;
+}
+
+impl WinePathExt for NativePath {
+    fn to_wine<M: PathMapper + ?Sized>(&self, mapper: &M) -> Result<WinePath, WinePathError> {
+        mapper.to_wine(self)
+    }
+}
+
+/// Convert wine paths with method syntax. Implemented for [`WinePath`] and [`str`], and so for
+/// [`String`] too.
+///
+/// ```rust
+/// use winepath::{NativePathExt, WineConfig};
+/// use std::path::PathBuf;
+/// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+/// ]);
+/// let path = r"c:\windows".to_native(&config).unwrap();
+/// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c/windows"));
+/// ```
+pub trait NativePathExt {
+    /// Convert a wine path to a native path, using a [`WineConfig`][crate::WineConfig] or another
+    /// [`PathMapper`]. See [`WineConfig::to_native_path`][crate::WineConfig::to_native_path].
+    fn to_native<M: PathMapper + ?Sized>(&self, mapper: &M) -> Result<PathBuf, WinePathError>;
+}
+
+impl NativePathExt for WinePath {
+    fn to_native<M: PathMapper + ?Sized>(&self, mapper: &M) -> Result<PathBuf, WinePathError> {
+        mapper.to_native(self)
+    }
+}
+
+impl NativePathExt for str {
+    fn to_native<M: PathMapper + ?Sized>(&self, mapper: &M) -> Result<PathBuf, WinePathError> {
+        mapper.to_native(&WinePath::from(self))
+    }
+}
+
+/// Convert the paths in an iterator lazily. Implemented for all iterators.
+///
+/// ```rust
+/// use winepath::{PathIteratorExt, WineConfig};
+/// use std::path::PathBuf;
+/// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+/// ]);
+/// let native_paths = vec![
+///     PathBuf::from("/home/username/.wine/drive_c/windows"),
+///     PathBuf::from("/home/username/.wine/drive_c/users"),
+/// ];
+/// let wine_paths: Vec<String> = native_paths
+///     .iter()
+///     .map_to_wine(&config)
+///     .map(|path| path.unwrap().to_string())
+///     .collect();
+/// assert_eq!(wine_paths, [r"c:\windows", r"c:\users"]);
+/// ```
+pub trait PathIteratorExt: Iterator + Sized {
+    /// Convert each native path to a wine path, using a [`WineConfig`][crate::WineConfig] or
+    /// another [`PathMapper`].
+    fn map_to_wine<M: PathMapper + ?Sized>(self, mapper: &M) -> MapToWine<'_, Self, M>
+    where
+        Self::Item: AsRef<NativePath>,
+    {
+        MapToWine { iter: self, mapper }
+    }
+
+    /// Convert each wine path to a native path, using a [`WineConfig`][crate::WineConfig] or
+    /// another [`PathMapper`].
+    fn map_to_native<M: PathMapper + ?Sized>(self, mapper: &M) -> MapToNative<'_, Self, M>
+    where
+        Self::Item: AsRef<str>,
+    {
+        MapToNative { iter: self, mapper }
+    }
+}
+
+impl<I: Iterator> PathIteratorExt for I {}
+
+/// Iterator returned by [`PathIteratorExt::map_to_wine`].
+#[derive(Debug, Clone)]
+pub struct MapToWine<'m, I, M: ?Sized> {
+    iter: I,
+    mapper: &'m M,
+}
+
+impl<I, M> Iterator for MapToWine<'_, I, M>
+where
+    I: Iterator,
+    I::Item: AsRef<NativePath>,
+    M: PathMapper + ?Sized,
+{
+    type Item = Result<WinePath, WinePathError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let path = self.iter.next()?;
+        Some(self.mapper.to_wine(path.as_ref()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Iterator returned by [`PathIteratorExt::map_to_native`].
+#[derive(Debug, Clone)]
+pub struct MapToNative<'m, I, M: ?Sized> {
+    iter: I,
+    mapper: &'m M,
+}
+
+impl<I, M> Iterator for MapToNative<'_, I, M>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+    M: PathMapper + ?Sized,
+{
+    type Item = Result<PathBuf, WinePathError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let path = self.iter.next()?;
+        Some(self.mapper.to_native(&WinePath::from(path.as_ref())))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
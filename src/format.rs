@@ -0,0 +1,117 @@
+use crate::wine_path::EXTENDED_PREFIX;
+use crate::{NativePath, WinePath};
+
+/// Letter case of the drive letter in wine paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DriveLetterCase {
+    /// `c:\windows`, like Wine's `winepath` tool.
+    #[default]
+    Lower,
+    /// `C:\windows`, like Windows Explorer.
+    Upper,
+}
+
+/// Separator between components in wine paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeparatorStyle {
+    /// `c:\windows\system32`.
+    #[default]
+    Backslash,
+    /// `c:/windows/system32`. Most Windows APIs accept this too.
+    Slash,
+}
+
+/// When to add a trailing separator to wine paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingSeparator {
+    /// Never add a trailing separator: drive roots are rendered as `c:`.
+    #[default]
+    Never,
+    /// Only add a trailing separator to drive roots, like `c:\`.
+    RootOnly,
+    /// Add a trailing separator to all paths that are directories on the native filesystem.
+    Directories,
+}
+
+/// When to add the `\\?\` extended-length prefix to wine paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LongPathPrefix {
+    /// Never add the prefix.
+    #[default]
+    Never,
+    /// Always add the prefix.
+    Always,
+    /// Add the prefix if the path is too long for most Windows APIs. See
+    /// [`WinePath::exceeds_max_path`].
+    WhenTooLong,
+}
+
+/// Options for rendering wine paths produced by [`WineConfig::to_wine_path`].
+///
+/// The default options produce the same paths as Wine's `winepath` tool.
+///
+/// ```rust
+/// use winepath::{DriveLetterCase, TrailingSeparator, WineConfig, WineFormatOptions};
+/// let mut config = WineConfig::from_prefix("/home/username/.wine");
+/// config.set_format_options(WineFormatOptions {
+///     drive_letter_case: DriveLetterCase::Upper,
+///     trailing_separator: TrailingSeparator::RootOnly,
+///     ..Default::default()
+/// });
+/// ```
+///
+/// [`WineConfig::to_wine_path`]: crate::WineConfig::to_wine_path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WineFormatOptions {
+    /// Letter case of the drive letter.
+    pub drive_letter_case: DriveLetterCase,
+    /// Separator between components. This is ignored for paths with the `\\?\` prefix, which
+    /// always use backslashes.
+    pub separator: SeparatorStyle,
+    /// When to add a trailing separator.
+    pub trailing_separator: TrailingSeparator,
+    /// When to add the `\\?\` extended-length prefix.
+    pub long_path_prefix: LongPathPrefix,
+}
+
+impl WineFormatOptions {
+    /// Render a wine path converted from `native` according to these options.
+    pub(crate) fn apply(&self, mut path: String, native: &NativePath) -> String {
+        let is_drive_path = path.as_bytes().get(1) == Some(&b':');
+        if is_drive_path {
+            match self.drive_letter_case {
+                DriveLetterCase::Lower => path[..1].make_ascii_lowercase(),
+                DriveLetterCase::Upper => path[..1].make_ascii_uppercase(),
+            }
+        }
+
+        let add_separator = match self.trailing_separator {
+            TrailingSeparator::Never => false,
+            TrailingSeparator::RootOnly => is_drive_path && path.len() == 2,
+            TrailingSeparator::Directories => native.is_dir(),
+        };
+        if add_separator && !path.ends_with('\\') {
+            path.push('\\');
+        }
+
+        let path = match self.long_path_prefix {
+            LongPathPrefix::Never => WinePath(path),
+            LongPathPrefix::Always => WinePath(path).to_extended_length(),
+            LongPathPrefix::WhenTooLong => {
+                let path = WinePath(path);
+                if path.exceeds_max_path() {
+                    path.to_extended_length()
+                } else {
+                    path
+                }
+            }
+        };
+
+        match self.separator {
+            SeparatorStyle::Slash if !path.0.starts_with(EXTENDED_PREFIX) => {
+                path.0.replace('\\', "/")
+            }
+            _ => path.0,
+        }
+    }
+}
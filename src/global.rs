@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::{NativePath, WineConfig, WinePath, WinePathError};
+
+/// The process-wide config used by [`to_wine`] and [`to_native`].
+static DEFAULT_CONFIG: OnceLock<WineConfig> = OnceLock::new();
+
+/// Get the process-wide config used by [`to_wine`] and [`to_native`].
+///
+/// The first successful call creates it with [`WineConfig::from_env`], unless one was set with
+/// [`set_default_config`]. If the prefix can't be determined, the error is returned, and the next
+/// call tries again.
+///
+/// ```rust,no_run
+/// let config = winepath::default_config().unwrap();
+/// println!("{}", config.prefix().display());
+/// ```
+pub fn default_config() -> Result<&'static WineConfig, WinePathError> {
+    if let Some(config) = DEFAULT_CONFIG.get() {
+        return Ok(config);
+    }
+    let config = WineConfig::from_env()?;
+    // Another thread may have won the race; then its config is used.
+    Ok(DEFAULT_CONFIG.get_or_init(|| config))
+}
+
+/// Set the process-wide config used by [`to_wine`] and [`to_native`], instead of reading it from
+/// the environment.
+///
+/// This must happen before the config is first used. Returns the config back as an error if the
+/// process-wide config was already set.
+///
+/// ```rust
+/// use winepath::WineConfig;
+/// use std::path::PathBuf;
+/// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+/// ]);
+/// winepath::set_default_config(config).unwrap();
+/// let path = winepath::to_native(r"c:\windows").unwrap();
+/// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c/windows"));
+/// ```
+pub fn set_default_config(config: WineConfig) -> Result<(), WineConfig> {
+    DEFAULT_CONFIG.set(config)
+}
+
+/// Convert a native file path to a Wine path, using the [process-wide config][default_config].
+///
+/// ```rust,no_run
+/// let path = winepath::to_wine("/home/username/.wine/drive_c/windows").unwrap();
+/// assert_eq!(path.to_string(), r"c:\windows");
+/// ```
+pub fn to_wine(path: impl AsRef<NativePath>) -> Result<WinePath, WinePathError> {
+    default_config()?.to_wine_path(path)
+}
+
+/// Convert a Wine path to a native file path, using the [process-wide config][default_config].
+///
+/// ```rust,no_run
+/// use std::path::PathBuf;
+/// let path = winepath::to_native(r"c:\windows").unwrap();
+/// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c/windows"));
+/// ```
+pub fn to_native(path: impl Into<WinePath>) -> Result<PathBuf, WinePathError> {
+    default_config()?.to_native_path(path)
+}
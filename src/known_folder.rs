@@ -0,0 +1,57 @@
+/// Registry key in `user.reg` with the paths of the known folders.
+pub(crate) const SHELL_FOLDERS_KEY: &str =
+    r"Software\Microsoft\Windows\CurrentVersion\Explorer\Shell Folders";
+
+/// A special folder in the user profile, like the desktop. See [`WineConfig::known_folder`].
+///
+/// [`WineConfig::known_folder`]: crate::WineConfig::known_folder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnownFolder {
+    /// `C:\users\<user>\Desktop`.
+    Desktop,
+    /// `C:\users\<user>\Documents`. Many games store save files here.
+    Documents,
+    /// `C:\users\<user>\Downloads`.
+    Downloads,
+    /// `C:\users\<user>\Music`.
+    Music,
+    /// `C:\users\<user>\Pictures`.
+    Pictures,
+    /// `C:\users\<user>\Videos`.
+    Videos,
+    /// `C:\users\<user>\AppData\Roaming`.
+    AppData,
+    /// `C:\users\<user>\AppData\Local`.
+    LocalAppData,
+    /// `C:\users\<user>\Favorites`.
+    Favorites,
+    /// `C:\users\<user>\AppData\Roaming\Microsoft\Windows\Start Menu`.
+    StartMenu,
+    /// The `Programs` folder in the start menu.
+    Programs,
+    /// The `Startup` folder in the start menu.
+    Startup,
+    /// `C:\users\<user>\AppData\Roaming\Microsoft\Windows\Templates`.
+    Templates,
+}
+
+impl KnownFolder {
+    /// Get the value name of the folder in the `Shell Folders` registry key.
+    pub(crate) fn registry_name(self) -> &'static str {
+        match self {
+            KnownFolder::Desktop => "Desktop",
+            KnownFolder::Documents => "Personal",
+            KnownFolder::Downloads => "{374DE290-123F-4565-9164-39C4925E467B}",
+            KnownFolder::Music => "My Music",
+            KnownFolder::Pictures => "My Pictures",
+            KnownFolder::Videos => "My Videos",
+            KnownFolder::AppData => "AppData",
+            KnownFolder::LocalAppData => "Local AppData",
+            KnownFolder::Favorites => "Favorites",
+            KnownFolder::StartMenu => "Start Menu",
+            KnownFolder::Programs => "Programs",
+            KnownFolder::Startup => "Startup",
+            KnownFolder::Templates => "Templates",
+        }
+    }
+}
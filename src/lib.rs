@@ -3,68 +3,232 @@
 //! This crate implements the conversion logic in much the same way as Wine itself.
 //!
 //! > Only for use on systems that have Wine!
+//!
+//! ## Features
+//! * `discover-bottles`, `discover-crossover`, `discover-lutris`, `discover-playonlinux`,
+//!   `discover-steam`: find the prefixes of each frontend in [`discover::discover_prefixes`].
+//!   Enabled by default.
+//! * `differential`: add the [`differential`] module, to compare conversions with the real
+//!   `winepath` program.
+//! * `rayon`: add [`WineConfig::par_to_wine_paths`] and [`WineConfig::par_to_native_paths`],
+//!   to convert large batches of paths in parallel.
+//! * `serde`: implement `Serialize` and `Deserialize` for [`WinePath`] and
+//!   [`WinePathErrorKind`].
+//! * `test-util`: add the [`testing`] module, to create temporary wine prefixes in tests.
+//! * `tracing`: emit [`tracing`](https://docs.rs/tracing) spans and events when resolving the
+//!   prefix, scanning the drives and converting paths.
+//! * `watch`: add `WatchedWineConfig`, which refreshes the drive mappings with inotify when the
+//!   `dosdevices` directory changes.
 use std::{
-    fmt::{self, Debug, Display, Formatter},
+    borrow::Cow,
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    fmt::Debug,
     path::{Component, Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock},
+};
+
+#[macro_use]
+mod trace;
+
+mod args;
+mod builder;
+mod cache;
+#[cfg(unix)]
+mod command;
+mod cwd;
+mod diagnose;
+#[cfg(all(feature = "differential", unix))]
+pub mod differential;
+pub mod discover;
+#[cfg(unix)]
+mod drive_cache;
+mod drive_info;
+mod drive_map;
+mod env_block;
+mod environment;
+mod error;
+mod explain;
+mod ext;
+mod format;
+mod global;
+mod known_folder;
+mod mapper;
+mod path_list;
+mod prefix_info;
+mod quote;
+mod registry;
+mod sanitize;
+mod short_name;
+mod steam;
+#[cfg(all(feature = "test-util", unix))]
+pub mod testing;
+#[cfg(feature = "watch")]
+mod watch;
+mod wine_environment;
+mod wine_path;
+
+pub use builder::WineConfigBuilder;
+use cache::{CacheKey, CacheValue};
+pub use cache::{CacheStats, ConversionCache};
+#[cfg(unix)]
+pub use command::WinepathCommand;
+pub use cwd::WineCwd;
+pub use diagnose::Diagnostic;
+pub use drive_info::{DriveInfo, DriveType};
+pub use drive_map::DriveMap;
+use drive_map::WineRoot;
+pub use env_block::{EnvPolicy, EnvVarPolicy};
+pub use error::{WinePathError, WinePathErrorKind};
+pub use explain::{Candidate, CandidateOutcome, Explanation};
+pub use ext::{MapToNative, MapToWine, NativePathExt, PathIteratorExt, WinePathExt};
+pub use format::{
+    DriveLetterCase, LongPathPrefix, SeparatorStyle, TrailingSeparator, WineFormatOptions,
+};
+pub use global::{default_config, set_default_config, to_native, to_wine};
+pub use known_folder::KnownFolder;
+pub use mapper::PathMapper;
+pub use prefix_info::WineArch;
+pub use quote::{join_args, quote_arg, quote_cmd_arg};
+use registry::RegistryFile;
+pub use sanitize::{sanitize_filename, sanitize_wine_path, SanitizeStrategy};
+use sanitize::{wine_escape, wine_unescape};
+use short_name::{find_long_name, short_name};
+#[cfg(feature = "watch")]
+pub use watch::WatchedWineConfig;
+pub use wine_environment::WineEnvironment;
+use wine_path::{
+    is_verbatim, path_kind, split_root, str_eq_ignore_case, strip_prefix_ignore_ascii_case,
+    DEVICE_PREFIX, EXTENDED_PREFIX, NT_PREFIX, UNIX_DEVICE_PREFIX,
 };
+pub use wine_path::{
+    ParseWinePathError, WineComponent, WinePath, WinePathKind, WinePathPrefix, WinePathViolation,
+};
+
+/// Location of the default prefix of the `org.winehq.Wine` Flatpak, relative to the home
+/// directory.
+pub(crate) const FLATPAK_PREFIX: &str = ".var/app/org.winehq.Wine/data/wine";
+
+/// Registry key in `system.reg` with the profile directories of the users.
+const PROFILE_LIST_KEY: &str = r"Software\Microsoft\Windows NT\CurrentVersion\ProfileList";
 
 /// A native path on the host system.
 type NativePath = Path;
 
-/// A file path within Wine. Wrapper around a string.
-///
-/// ```rust
-/// use winepath::WinePath;
-/// let wine_path = WinePath(r"C:\windows\system32\ddraw.dll".to_string());
-/// ```
-#[derive(Debug, Clone)]
-pub struct WinePath(pub String);
-impl AsRef<str> for WinePath {
-    fn as_ref(&self) -> &str {
-        &self.0
+/// Convert the part of a `\??\unix\` or `\\?\unix\` path after the prefix to a native path.
+fn unix_to_native_path(unix_path: &str) -> PathBuf {
+    let mut path = PathBuf::from("/");
+    for part in unix_path.split('\\') {
+        path.push(wine_unescape(part).as_ref());
     }
+    path
 }
-impl From<String> for WinePath {
-    fn from(string: String) -> Self {
-        Self(string)
+
+/// Canonicalize a native path according to `policy`.
+fn canonicalize(
+    path: &NativePath,
+    policy: CanonicalizePolicy,
+) -> Result<Cow<'_, NativePath>, WinePathError> {
+    match policy {
+        CanonicalizePolicy::Never => Ok(Cow::Borrowed(path)),
+        CanonicalizePolicy::Lexical => Ok(Cow::Owned(normalize_lexically(&make_absolute(path)))),
+        CanonicalizePolicy::Full => path.canonicalize().map(Cow::Owned).map_err(|source| {
+            WinePathError::CanonicalizeFailed {
+                path: path.to_path_buf(),
+                source,
+            }
+        }),
+        CanonicalizePolicy::ExistingAncestor => {
+            Ok(Cow::Owned(canonicalize_existing_ancestor(path)))
+        }
     }
 }
-impl From<&str> for WinePath {
-    fn from(string: &str) -> Self {
-        Self(string.to_string())
+
+/// Resolve a relative path against the current directory.
+fn make_absolute(path: &NativePath) -> PathBuf {
+    match std::env::current_dir() {
+        Ok(cwd) if path.is_relative() => cwd.join(path),
+        _ => path.to_path_buf(),
     }
 }
-impl Display for WinePath {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+
+/// Resolve `.` and `..` components without touching the filesystem.
+fn normalize_lexically(path: &NativePath) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
     }
+    normalized
 }
 
-/// Error type.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum WinePathError {
-    /// Could not determine the wine prefix to use.
-    PrefixNotFound,
-    /// No drive letter → file path mapping is available for the given path.
-    NoDrive,
+/// Canonicalize the deepest existing ancestor of `path`, and append the remaining components
+/// lexically.
+///
+/// Relative paths are resolved against the current directory.
+fn canonicalize_existing_ancestor(path: &NativePath) -> PathBuf {
+    let absolute = make_absolute(path);
+    let components: Vec<Component<'_>> = absolute.components().collect();
+    for split in (1..=components.len()).rev() {
+        let ancestor: PathBuf = components[..split].iter().collect();
+        if let Ok(mut resolved) = ancestor.canonicalize() {
+            for component in &components[split..] {
+                match component {
+                    Component::ParentDir => {
+                        resolved.pop();
+                    }
+                    Component::Normal(part) => resolved.push(part),
+                    _ => {}
+                }
+            }
+            return resolved;
+        }
+    }
+    absolute
 }
 
-impl Display for WinePathError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self {
-            WinePathError::PrefixNotFound => write!(f, "could not determine wine prefix"),
-            WinePathError::NoDrive => write!(f, "native path is not mapped to a wine drive"),
-        }
+/// Expand a leading `~` in a `WINEPREFIX` to the home directory, and resolve relative paths
+/// against the working directory, or the current directory if it is not known.
+fn expand_prefix(
+    prefix: PathBuf,
+    home: Option<OsString>,
+    cwd: Option<OsString>,
+) -> Option<PathBuf> {
+    let prefix = match prefix.strip_prefix("~") {
+        Ok(rest) => PathBuf::from(home?).join(rest),
+        Err(_) => prefix,
+    };
+    match cwd {
+        Some(cwd) if prefix.is_relative() => Some(PathBuf::from(cwd).join(prefix)),
+        _ => Some(make_absolute(&prefix)),
     }
 }
 
-impl std::error::Error for WinePathError {}
+/// Find the wine prefix in a Proton `compatdata` directory.
+fn proton_prefix(compat_data: PathBuf) -> PathBuf {
+    if compat_data.ends_with("pfx") {
+        compat_data
+    } else {
+        compat_data.join("pfx")
+    }
+}
 
-fn default_wineprefix() -> Option<PathBuf> {
-    std::env::var_os("HOME").map(PathBuf::from).map(|mut home| {
-        home.push(".wine");
-        home
-    })
+/// Find the default wine prefix in the home directory: `~/.wine`, or the prefix of the
+/// `org.winehq.Wine` Flatpak if only that exists.
+fn default_wineprefix(home: Option<OsString>) -> Option<PathBuf> {
+    let home = PathBuf::from(home?);
+    let prefix = home.join(".wine");
+    let flatpak_prefix = home.join(FLATPAK_PREFIX);
+    if !prefix.is_dir() && flatpak_prefix.is_dir() {
+        Some(flatpak_prefix)
+    } else {
+        Some(prefix)
+    }
 }
 
 const ASCII_A: u8 = 0x61;
@@ -78,94 +242,353 @@ fn index_to_drive(index: usize) -> char {
     char::from(ASCII_A + index as u8)
 }
 
-/// Stringify a native path, Windows-style.
-fn stringify_path(drive_prefix: &str, path: &NativePath) -> String {
-    let parts = path.components().map(|c| match c {
-        Component::RootDir => "",
-        // `path` is not a windows path
-        Component::Prefix(_) => unreachable!(),
-        Component::CurDir => ".",
-        Component::ParentDir => "..",
-        Component::Normal(part) => part.to_str().expect("path is not utf-8"),
-    });
-
-    std::iter::once(drive_prefix)
-        .chain(parts)
-        .collect::<Vec<&str>>()
-        .join(r"\")
+/// Resolve a native path case-insensitively, like Wine looks up files.
+fn resolve_case_insensitive(path: PathBuf) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    let mut components = path.components();
+    for component in &mut components {
+        if let Component::Normal(name) = component {
+            if resolved.join(name).symlink_metadata().is_err() {
+                match name
+                    .to_str()
+                    .and_then(|name| find_long_name(&resolved, name))
+                {
+                    Some(entry) => resolved.push(entry),
+                    None => {
+                        // The rest of the path doesn't exist, so keep it as is.
+                        resolved.push(name);
+                        break;
+                    }
+                }
+                continue;
+            }
+        }
+        resolved.push(component);
+    }
+    resolved.extend(components);
+    resolved
 }
 
-#[derive(Default)]
-struct DriveCache {
-    drives: [Option<PathBuf>; 26],
+/// Stringify a native path, Windows-style.
+///
+/// Characters that are not allowed in Windows file names are escaped like Wine does. Returns
+/// `None` if a component is not valid UTF-8, unless `lossy` is set: then invalid sequences are
+/// replaced with U+FFFD.
+fn stringify_path(drive_prefix: &str, path: &NativePath, lossy: bool) -> Option<String> {
+    let mut wine_path = String::new();
+    push_wine_path(&mut wine_path, drive_prefix, path, lossy).then_some(wine_path)
 }
 
-impl DriveCache {
-    fn from_prefix(prefix: &NativePath) -> Self {
-        let drives_dir = prefix.join("dosdevices");
-        let mut drive_cache = Self::default();
-
-        for letter in b'a'..=b'z' {
-            let drive_name = [letter, b':'];
-            let drive_name = std::str::from_utf8(&drive_name).unwrap();
-            let drive_dir = drives_dir.join(drive_name);
-            if let Ok(target) = drive_dir.read_link() {
-                if let Ok(resolved_path) = drives_dir.join(target).canonicalize() {
-                    drive_cache.drives[drive_to_index(char::from(letter))] = Some(resolved_path);
-                }
+/// Append the wine path for `path` on the drive or share `drive_prefix` to `wine_path`.
+///
+/// Returns `false` if `path` is not valid UTF-8 and `lossy` is not set. Then `wine_path` is left
+/// partially written.
+fn push_wine_path(
+    wine_path: &mut String,
+    drive_prefix: &str,
+    path: &NativePath,
+    lossy: bool,
+) -> bool {
+    wine_path.push_str(drive_prefix);
+    for c in path.components() {
+        wine_path.push('\\');
+        match c {
+            Component::RootDir => {}
+            // `path` is not a windows path
+            Component::Prefix(_) => unreachable!(),
+            Component::CurDir => wine_path.push('.'),
+            Component::ParentDir => wine_path.push_str(".."),
+            Component::Normal(part) if lossy => {
+                wine_path.push_str(&wine_escape(&part.to_string_lossy()))
             }
+            Component::Normal(part) => match part.to_str() {
+                Some(part) => wine_path.push_str(&wine_escape(part)),
+                None => return false,
+            },
         }
-        drive_cache
     }
+    true
+}
 
-    fn iter(&self) -> impl Iterator<Item = (char, &Path)> {
-        self.drives.iter().enumerate().filter_map(|(index, path)| {
-            path.as_ref()
-                .map(|path| (index_to_drive(index), path.as_ref()))
-        })
-    }
+/// How native paths are canonicalized before converting them to wine paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CanonicalizePolicy {
+    /// Use the path as is. It must be absolute and should not contain symlinks or `..`
+    /// components.
+    #[default]
+    Never,
+    /// Make the path absolute and resolve `.` and `..` components, without touching the
+    /// filesystem. Symlinks are not resolved.
+    Lexical,
+    /// Resolve symlinks with [`std::fs::canonicalize`]. The path must exist.
+    Full,
+    /// Resolve symlinks in the deepest existing ancestor, and append the remaining components
+    /// lexically. The path does not need to exist.
+    ExistingAncestor,
+}
 
-    fn get(&self, drive_letter: char) -> Option<&Path> {
-        self.drives
-            .get(drive_to_index(drive_letter))
-            .and_then(|path| path.as_ref().map(|path| path.as_ref()))
-    }
+/// Which drive to use when a native path is on several drives.
+///
+/// All preferences fall back to the most specific drive, then to the first drive letter.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum DrivePreference {
+    /// Prefer the drive whose root is deepest in the native file system, like Wine: `d:` mapped
+    /// to `/home/me/games` wins over `z:` mapped to `/`.
+    #[default]
+    MostSpecific,
+    /// Prefer the lowest drive letter. UNC shares come after all drives.
+    LowestLetter,
+    /// Prefer any drive over `z:`, which usually maps the whole file system.
+    AvoidZ,
+    /// Prefer drives in the given order. Drives that are not in the list come last.
+    Priority(Vec<char>),
+    /// Prefer the drive that results in the shortest wine path.
+    ShortestPath,
 }
 
-impl Debug for DriveCache {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut s = f.debug_struct("DriveCache");
-        for (drive_letter, path) in self.iter() {
-            s.field(std::str::from_utf8(&[drive_letter as u8]).unwrap(), &path);
+impl DrivePreference {
+    /// Rank a root that `remaining` is in. Lower ranks are preferred.
+    fn rank(&self, root: WineRoot<'_>, remaining: &NativePath) -> usize {
+        let drive = match root {
+            WineRoot::Drive(letter) => Some(letter),
+            WineRoot::Unc(..) => None,
+        };
+        match self {
+            DrivePreference::MostSpecific => 0,
+            DrivePreference::LowestLetter => drive.map_or(26, drive_to_index),
+            DrivePreference::AvoidZ => usize::from(drive == Some('z')),
+            DrivePreference::Priority(letters) => drive
+                .and_then(|drive| {
+                    letters
+                        .iter()
+                        .position(|letter| letter.eq_ignore_ascii_case(&drive))
+                })
+                .unwrap_or(letters.len()),
+            DrivePreference::ShortestPath => root.to_string().len() + remaining.as_os_str().len(),
         }
-        s.finish()
     }
 }
 
+/// Which drive to use for native paths that are not on a more specific drive.
+///
+/// Wine maps `z:` to `/` by default, so every native path has a wine path. Such drives that map
+/// the whole file system are only used as a fallback: a more specific drive always wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FallbackDrive {
+    /// Use the drives that are mapped to `/` in the prefix, usually `z:`. Paths are not
+    /// converted if there is no such drive.
+    #[default]
+    Mapped,
+    /// Use this drive, as if it were mapped to `/`. Other drives that are mapped to `/` are
+    /// ignored. If the drive is mapped to another directory, paths outside of the mapped
+    /// drives are not converted.
+    Drive(char),
+    /// Only use drives that map a specific directory. Drives that are mapped to `/` are ignored,
+    /// so paths outside of the mapped directories are not converted.
+    Disabled,
+}
+
 /// The main conversion struct: create one of these to do conversions.
 ///
 /// Tracks the WINEPREFIX and the drive letter mappings so they don't have to be recomputed every
-/// time you convert a path.
-#[derive(Debug)]
+/// time you convert a path. The drive mappings are read from the prefix when they are first
+/// needed, so creating a config is cheap.
+///
+/// A config can be shared between threads. Clones are cheap, and share the drive mappings:
+/// refreshing them in one clone refreshes them in all clones.
+///
+/// ```rust
+/// use winepath::WineConfig;
+/// use std::path::PathBuf;
+/// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+/// ]);
+/// let worker = {
+///     let config = config.clone();
+///     std::thread::spawn(move || config.to_native_path(r"c:\windows").unwrap())
+/// };
+/// assert_eq!(worker.join().unwrap(), PathBuf::from("/home/username/.wine/drive_c/windows"));
+/// ```
+#[derive(Debug, Clone)]
 pub struct WineConfig {
     prefix: PathBuf,
-    drive_cache: DriveCache,
+    /// The drive mappings, or `None` if they were not read yet. Conversions take a snapshot, so
+    /// the lock is only held briefly.
+    drive_map: Arc<RwLock<Option<Arc<DriveMap>>>>,
+    canonicalize_policy: CanonicalizePolicy,
+    format_options: WineFormatOptions,
+    case_insensitive: bool,
+    drive_preference: DrivePreference,
+    fallback_drive: FallbackDrive,
+    default_mappings: bool,
+    /// The conversion cache, if enabled. Clones share it until their settings are changed.
+    cache: Option<Arc<ConversionCache>>,
 }
 
 impl WineConfig {
+    /// Start building a config with custom settings. See [`WineConfigBuilder`].
+    pub fn builder() -> WineConfigBuilder {
+        WineConfigBuilder::new()
+    }
+
     /// Determine the wine prefix from the environment.
+    ///
+    /// The prefix is read from `WINEPREFIX`, and defaults to `~/.wine`. If that does not exist,
+    /// the default prefix of the `org.winehq.Wine` Flatpak in
+    /// `~/.var/app/org.winehq.Wine/data/wine` is used, if it exists. When running under Steam,
+    /// `WINEPREFIX` is usually not set, so the Proton prefix in `STEAM_COMPAT_DATA_PATH` is used
+    /// instead, like [`WineConfig::from_proton_compatdata`]. A leading `~` in
+    /// `WINEPREFIX` is expanded to the home directory, and relative paths are resolved against
+    /// the current directory, like a shell would. Returns [`WinePathError::PrefixNotFound`] if
+    /// the prefix directory does not exist.
     pub fn from_env() -> Result<Self, WinePathError> {
-        let prefix = std::env::var_os("WINEPREFIX")
-            .map(PathBuf::from)
-            .or_else(default_wineprefix)
-            .ok_or(WinePathError::PrefixNotFound)?;
+        // `PWD` is inherited from the parent and goes stale after `chdir`, so relative paths are
+        // resolved against the real working directory.
+        Self::from_env_with(|name| std::env::var_os(name), None)
+    }
+
+    /// Determine the wine prefix from a captured environment, like the environment of another
+    /// process, instead of the environment of the current process.
+    ///
+    /// Relative `WINEPREFIX` paths are resolved against `PWD` from the environment, if it is
+    /// set. See [`WineConfig::from_env`].
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::collections::HashMap;
+    /// use std::ffi::OsString;
+    /// use std::path::Path;
+    /// let mut env = HashMap::new();
+    /// env.insert(OsString::from("HOME"), OsString::from("/home/username"));
+    /// let config = WineConfig::from_env_map(&env).unwrap();
+    /// assert_eq!(config.prefix(), Path::new("/home/username/.wine"));
+    /// ```
+    pub fn from_env_map(env: &HashMap<OsString, OsString>) -> Result<Self, WinePathError> {
+        Self::from_env_with(
+            |name| env.get(OsStr::new(name)).cloned(),
+            env.get(OsStr::new("PWD")).cloned(),
+        )
+    }
+
+    /// Determine the wine prefix of a running process, from its environment in
+    /// `/proc/<pid>/environ`.
+    ///
+    /// Relative `WINEPREFIX` paths are resolved against the working directory of the process.
+    /// Returns [`WinePathError::Io`] if the environment of the process can't be read, e.g.
+    /// because it belongs to another user.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_pid(1234).unwrap();
+    /// println!("{}", config.prefix().display());
+    /// ```
+    #[cfg(unix)]
+    pub fn from_pid(pid: u32) -> Result<Self, WinePathError> {
+        use std::os::unix::ffi::OsStrExt;
 
-        let drive_cache = DriveCache::from_prefix(&prefix);
+        let _span = debug_span!("from_pid", pid);
+        let proc_dir = PathBuf::from(format!("/proc/{}", pid));
+        let environ = std::fs::read(proc_dir.join("environ"))?;
+        let mut env: HashMap<OsString, OsString> = environ
+            .split(|&byte| byte == 0)
+            .filter_map(|var| {
+                let separator = var.iter().position(|&byte| byte == b'=')?;
+                let name = OsStr::from_bytes(&var[..separator]);
+                let value = OsStr::from_bytes(&var[separator + 1..]);
+                Some((name.to_os_string(), value.to_os_string()))
+            })
+            .collect();
+        // `PWD` is not updated when the process changes directories.
+        if let Ok(cwd) = proc_dir.join("cwd").read_link() {
+            env.insert(OsString::from("PWD"), cwd.into_os_string());
+        }
+        Self::from_env_map(&env)
+    }
 
-        Ok(Self {
-            prefix,
-            drive_cache,
-        })
+    /// Determine the wine prefix from environment variables. Relative paths are resolved against
+    /// `cwd`, or the current directory if it is `None`.
+    fn from_env_with(
+        var: impl Fn(&str) -> Option<OsString>,
+        cwd: Option<OsString>,
+    ) -> Result<Self, WinePathError> {
+        let prefix = match (var("WINEPREFIX"), var("STEAM_COMPAT_DATA_PATH")) {
+            (Some(prefix), _) => {
+                debug_event!(?prefix, "using WINEPREFIX");
+                expand_prefix(prefix.into(), var("HOME"), cwd)
+            }
+            (None, Some(compat_data)) => {
+                debug_event!(?compat_data, "using STEAM_COMPAT_DATA_PATH");
+                expand_prefix(compat_data.into(), var("HOME"), cwd).map(proton_prefix)
+            }
+            (None, None) => {
+                debug_event!("using the default prefix");
+                default_wineprefix(var("HOME"))
+            }
+        }
+        .ok_or(WinePathError::PrefixNotFound)?;
+        if !prefix.is_dir() {
+            debug_event!(prefix = %prefix.display(), "wine prefix does not exist");
+            return Err(WinePathError::PrefixNotFound);
+        }
+        debug_event!(prefix = %prefix.display(), "resolved wine prefix");
+
+        Ok(Self::from_prefix(prefix))
+    }
+
+    /// Create a config for a Proton prefix, from its Steam `compatdata` directory.
+    ///
+    /// Proton keeps the wine prefix in the `pfx` subdirectory, next to its own files. The path
+    /// can be the `compatdata` directory or the `pfx` directory itself.
+    ///
+    /// ```rust
+    /// use winepath::WineConfig;
+    /// use std::path::Path;
+    /// let config = WineConfig::from_proton_compatdata("/home/username/.steam/steam/steamapps/compatdata/570");
+    /// assert_eq!(config.prefix(), Path::new("/home/username/.steam/steam/steamapps/compatdata/570/pfx"));
+    /// ```
+    pub fn from_proton_compatdata(path: impl Into<PathBuf>) -> Self {
+        Self::from_prefix(proton_prefix(path.into()))
+    }
+
+    /// Create a config for the Proton prefix of a Steam game, by its app ID.
+    ///
+    /// The Steam libraries are read from `libraryfolders.vdf` in the Steam installation in the
+    /// home directory, including the Flatpak version of Steam. The prefix is looked up in the
+    /// library that the game is installed in first, and then in the other libraries. Returns
+    /// [`WinePathError::PrefixNotFound`] if the game has no Proton prefix.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::Path;
+    /// let config = WineConfig::from_steam_app_id(570).unwrap();
+    /// assert_eq!(config.prefix(), Path::new("/home/username/.local/share/Steam/steamapps/compatdata/570/pfx"));
+    /// ```
+    pub fn from_steam_app_id(app_id: u32) -> Result<Self, WinePathError> {
+        let home = std::env::var_os("HOME").ok_or(WinePathError::PrefixNotFound)?;
+        steam::find_compat_data(Path::new(&home), app_id)
+            .map(Self::from_proton_compatdata)
+            .ok_or(WinePathError::PrefixNotFound)
+    }
+
+    /// Find the wine prefix that contains a native path, like a game executable in `drive_c`.
+    ///
+    /// The parent directories of the path are searched for a prefix: a directory with a
+    /// `dosdevices` directory. Returns [`WinePathError::PrefixNotFound`] if there is none.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::Path;
+    /// let config = WineConfig::from_containing_prefix("/home/username/Games/cool-game/drive_c/CoolGame/game.exe").unwrap();
+    /// assert_eq!(config.prefix(), Path::new("/home/username/Games/cool-game"));
+    /// ```
+    pub fn from_containing_prefix(path: impl AsRef<NativePath>) -> Result<Self, WinePathError> {
+        let path = normalize_lexically(&make_absolute(path.as_ref()));
+        let prefix = path
+            .ancestors()
+            .find(|dir| dir.join("dosdevices").is_dir())
+            .ok_or(WinePathError::PrefixNotFound)?;
+        debug_event!(path = %path.display(), prefix = %prefix.display(), "found containing prefix");
+        Ok(Self::from_prefix(prefix))
     }
 
     /// Create a config assuming that the given path is a valid WINEPREFIX.
@@ -181,94 +604,1857 @@ impl WineConfig {
     /// }
     /// ```
     pub fn from_prefix(path: impl Into<PathBuf>) -> Self {
-        let prefix: PathBuf = path.into();
-        let drive_cache = DriveCache::from_prefix(&prefix);
-
         Self {
-            prefix,
-            drive_cache,
+            prefix: path.into(),
+            drive_map: Arc::default(),
+            canonicalize_policy: CanonicalizePolicy::default(),
+            format_options: WineFormatOptions::default(),
+            case_insensitive: false,
+            drive_preference: DrivePreference::default(),
+            fallback_drive: FallbackDrive::default(),
+            default_mappings: false,
+            cache: None,
         }
     }
 
-    /// Get the current wine prefix.
-    pub fn prefix(&self) -> &NativePath {
-        &self.prefix
+    /// Create a config with the given drive mappings, without reading them from the prefix.
+    ///
+    /// Path conversions don't touch the filesystem, unless a [`CanonicalizePolicy`] or
+    /// [case-insensitive][WineConfig::set_case_insensitive] lookups are enabled. This is useful
+    /// for tests, or for converting paths in a prefix on another machine. Methods that read other
+    /// files in the prefix, like [`WineConfig::known_folder`], still read them from `prefix`.
+    ///
+    /// The paths should be canonical, like in [`DriveMap::insert`]. Panics if a drive letter is
+    /// not an ASCII letter.
+    ///
+    /// ```rust
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    ///     ('z', PathBuf::from("/")),
+    /// ]);
+    /// let path = config.to_wine_path("/home/username/.wine/drive_c/windows").unwrap();
+    /// assert_eq!(path.to_string(), r"c:\windows");
+    /// let path = config.to_native_path(r"Z:\etc\hosts").unwrap();
+    /// assert_eq!(path, PathBuf::from("/etc/hosts"));
+    /// ```
+    pub fn from_drive_map(
+        prefix: impl Into<PathBuf>,
+        drives: impl IntoIterator<Item = (char, PathBuf)>,
+    ) -> Self {
+        let config = Self::from_prefix(prefix);
+        config.set_drive_map(drives.into_iter().collect());
+        config
     }
 
-    fn find_drive_root<'p>(
-        &self,
-        path: &'p NativePath,
-    ) -> Result<(String, &'p NativePath), WinePathError> {
-        for (letter, root) in self.drive_cache.iter() {
-            // Returns `err` if `root` is not a parent of `path`.
-            if let Ok(remaining) = path.strip_prefix(root) {
-                let mut drive = String::new();
-                drive.push(letter);
-                drive.push(':');
-                return Ok((drive, remaining));
+    /// Rescan the `dosdevices` directory of the prefix, e.g. after the user changed the drives
+    /// in `winecfg`.
+    ///
+    /// This replaces any mappings set with [`WineConfig::set_drive_map`]. The directory is
+    /// scanned the next time the mappings are needed. Conversions that are running on other
+    /// threads finish with the old mappings.
+    pub fn refresh(&self) {
+        *self.write_drive_map() = None;
+    }
+
+    /// Rescan a single drive in the `dosdevices` directory of the prefix. The drive is unmapped
+    /// if it no longer exists.
+    ///
+    /// Panics if `drive_letter` is not an ASCII letter.
+    pub fn refresh_drive(&self, drive_letter: char) {
+        let drives_dir = self.prefix.join("dosdevices");
+        if let Some(drive_map) = self.write_drive_map().as_mut() {
+            let drive_map = Arc::make_mut(drive_map);
+            drive_map.read_drive(&drives_dir, drive_letter);
+            drive_map.fill_from_registry(&self.prefix, Some(drive_letter));
+            if self.default_mappings && !drives_dir.is_dir() {
+                drive_map.fill_defaults(&self.prefix, Some(drive_letter));
             }
         }
+    }
+
+    /// Map a drive to a native directory, by creating the `dosdevices/x:` symlink.
+    ///
+    /// An existing mapping for the drive is replaced. Relative targets are relative to the
+    /// `dosdevices` directory, like `../drive_c`.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// config.map_drive('d', "/mnt/data").unwrap();
+    /// let path = config.to_wine_path("/mnt/data/file.txt").unwrap();
+    /// assert_eq!(path.to_string(), r"d:\file.txt");
+    /// ```
+    ///
+    /// Returns an [`std::io::ErrorKind::InvalidInput`] error if `drive_letter` is not an ASCII
+    /// letter.
+    #[cfg(unix)]
+    pub fn map_drive(
+        &self,
+        drive_letter: char,
+        target: impl AsRef<NativePath>,
+    ) -> std::io::Result<()> {
+        let link = self.drive_link(drive_letter)?;
+        if link.symlink_metadata().is_ok() {
+            std::fs::remove_file(&link)?;
+        } else if let Some(drives_dir) = link.parent() {
+            std::fs::create_dir_all(drives_dir)?;
+        }
+        std::os::unix::fs::symlink(target, &link)?;
+        self.refresh_drive(drive_letter);
+        Ok(())
+    }
+
+    /// Unmap a drive, by removing the `dosdevices/x:` symlink. Does nothing if the drive is not
+    /// mapped.
+    ///
+    /// Returns an [`std::io::ErrorKind::InvalidInput`] error if `drive_letter` is not an ASCII
+    /// letter.
+    #[cfg(unix)]
+    pub fn unmap_drive(&self, drive_letter: char) -> std::io::Result<()> {
+        let link = self.drive_link(drive_letter)?;
+        match std::fs::remove_file(&link) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+        self.refresh_drive(drive_letter);
+        Ok(())
+    }
 
-        Err(WinePathError::NoDrive)
+    /// Find the first drive letter that is not in use, or `None` if all letters are taken.
+    ///
+    /// Letters with a `dosdevices` entry are in use, even if the entry is a broken symlink. Set
+    /// `skip_floppy` to never return `a` or `b`, which old programs treat as floppy drives.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let letter = config.next_free_drive(true).expect("no free drive letters");
+    /// config.map_drive(letter, "/mnt/data").unwrap();
+    /// ```
+    pub fn next_free_drive(&self, skip_floppy: bool) -> Option<char> {
+        let first = if skip_floppy { b'c' } else { b'a' };
+        (first..=b'z').map(char::from).find(|&letter| {
+            !self.drive_map().contains(letter)
+                && self
+                    .drive_link(letter)
+                    .is_ok_and(|link| link.symlink_metadata().is_err())
+        })
     }
 
-    fn to_wine_path_inner(&self, path: &NativePath) -> Result<String, WinePathError> {
-        let (root, remaining) = self.find_drive_root(path)?;
+    /// Convert a native path to a wine path, mapping a new drive if no drive covers it yet.
+    ///
+    /// The new drive is mapped to the path itself if it is a directory, or else to its deepest
+    /// existing ancestor directory. `a:` and `b:` are not used. The path does not need to exist.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// // If `/mnt/data` is not reachable yet, this maps it to e.g. `d:`.
+    /// let path = config.ensure_mapped("/mnt/data/setup.exe").unwrap();
+    /// assert_eq!(path.to_string(), r"d:\setup.exe");
+    /// ```
+    ///
+    /// Returns [`WinePathError::NoFreeDrive`] if all drive letters are in use, and
+    /// [`WinePathError::MapDriveFailed`] if the drive symlink could not be created. Paths that
+    /// could only be mapped through a drive for `/` return [`WinePathError::NoDrive`] if the
+    /// [`FallbackDrive`] setting ignores such drives, and no drive is mapped.
+    #[cfg(unix)]
+    pub fn ensure_mapped(&self, path: impl AsRef<NativePath>) -> Result<WinePath, WinePathError> {
+        let native = canonicalize_existing_ancestor(path.as_ref());
+        match self.to_wine_path_inner(&native, false) {
+            Err(WinePathError::NoDrive { .. }) => {}
+            result => return result.map(WinePath),
+        }
 
-        Ok(stringify_path(&root, remaining))
+        let mut root = native.as_path();
+        while !root.is_dir() {
+            root = root.parent().ok_or_else(|| self.no_drive_error(&native))?;
+        }
+        let letter = self
+            .next_free_drive(true)
+            .ok_or(WinePathError::NoFreeDrive)?;
+        // A new drive for `/` would be ignored, like the existing ones, because of the fallback
+        // drive setting.
+        if self.is_ignored_root(WineRoot::Drive(letter), root) {
+            return Err(self.no_drive_error(&native));
+        }
+        self.map_drive(letter, root)
+            .map_err(|source| WinePathError::MapDriveFailed {
+                path: root.to_path_buf(),
+                source,
+            })?;
+        self.to_wine_path_inner(&native, false).map(WinePath)
     }
 
-    fn to_native_path_inner(&self, path: &str) -> Result<PathBuf, WinePathError> {
-        // TODO resolve the path…maybe?
-        assert!(path.len() >= 2);
-        assert!(
-            char::from(path.as_bytes()[0]).is_ascii_alphabetic()
-                && char::from(path.as_bytes()[1]) == ':'
-        );
-        let full_path = path;
+    /// Get the path of the `dosdevices/x:` symlink for a drive.
+    fn drive_link(&self, drive_letter: char) -> std::io::Result<PathBuf> {
+        if !drive_letter.is_ascii_alphabetic() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "drive letter must be an ASCII letter",
+            ));
+        }
+        let drive_name = format!("{}:", drive_letter.to_ascii_lowercase());
+        Ok(self.prefix.join("dosdevices").join(drive_name))
+    }
 
-        let drive_letter = full_path.chars().next().unwrap();
-        if let Some(native_root) = self.drive_cache.get(drive_letter) {
-            let mut path = native_root.to_path_buf();
-            for part in full_path[2..].split('\\') {
-                path.push(part);
-            }
-            Ok(path)
-        } else {
-            Err(WinePathError::NoDrive)
+    /// Get the current wine prefix.
+    pub fn prefix(&self) -> &NativePath {
+        &self.prefix
+    }
+
+    /// Get the architecture of the prefix, from the header of its `system.reg` file.
+    ///
+    /// Returns `None` if the prefix has not been initialized by Wine yet.
+    ///
+    /// ```rust,no_run
+    /// use winepath::{WineArch, WineConfig};
+    /// let config = WineConfig::from_env().unwrap();
+    /// let dir_32bit = match config.arch() {
+    ///     Some(WineArch::Win64) => r"c:\windows\syswow64",
+    ///     _ => r"c:\windows\system32",
+    /// };
+    /// ```
+    pub fn arch(&self) -> Option<WineArch> {
+        RegistryFile::read(&self.prefix, "system.reg")
+            .as_ref()
+            .and_then(WineArch::from_registry)
+    }
+
+    /// Get the native path of the Windows directory, `C:\windows`.
+    pub fn windows_dir(&self) -> Option<PathBuf> {
+        self.to_native_path(r"C:\windows").ok()
+    }
+
+    /// Get the native path of `C:\windows\system32`.
+    ///
+    /// In 64-bit prefixes, this holds the 64-bit libraries. See [`WineConfig::system_dir`].
+    pub fn system32_dir(&self) -> Option<PathBuf> {
+        self.to_native_path(r"C:\windows\system32").ok()
+    }
+
+    /// Get the native path of `C:\windows\syswow64`, which holds the 32-bit libraries in
+    /// 64-bit prefixes. Returns `None` in 32-bit prefixes.
+    pub fn syswow64_dir(&self) -> Option<PathBuf> {
+        match self.arch() {
+            Some(WineArch::Win64) => self.to_native_path(r"C:\windows\syswow64").ok(),
+            _ => None,
         }
     }
 
-    /// Convert a native file path to a Wine path.
+    /// Get the native path of the system directory for programs of the given architecture,
+    /// taking WoW64 into account.
+    ///
+    /// 32-bit programs use `syswow64` in 64-bit prefixes, and `system32` in 32-bit prefixes.
+    /// 64-bit programs use `system32`. They can't run in 32-bit prefixes, so this returns `None`
+    /// for them there.
+    ///
+    /// ```rust,no_run
+    /// use winepath::{WineArch, WineConfig};
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_env().unwrap();
+    /// // In a 64-bit prefix:
+    /// let dir = config.system_dir(WineArch::Win32).unwrap();
+    /// assert_eq!(dir, PathBuf::from("/home/username/.wine/drive_c/windows/syswow64"));
+    /// ```
+    pub fn system_dir(&self, arch: WineArch) -> Option<PathBuf> {
+        match arch {
+            WineArch::Win32 => self.syswow64_dir().or_else(|| self.system32_dir()),
+            WineArch::Win64 => match self.arch() {
+                Some(WineArch::Win32) => None,
+                _ => self.system32_dir(),
+            },
+        }
+    }
+
+    /// Get the version of Wine or Proton that last updated the prefix.
+    ///
+    /// Plain Wine does not record its version in the prefix, so this only works for prefixes
+    /// managed by Proton (`proton-8.0-5`) or Bottles (`soda-7.0-9`). Returns `None` otherwise.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_prefix("/home/username/.steam/steam/steamapps/compatdata/570/pfx");
+    /// if let Some(version) = config.wine_version() {
+    ///     println!("last used with {}", version);
+    /// }
+    /// ```
+    pub fn wine_version(&self) -> Option<String> {
+        prefix_info::read_wine_version(&self.prefix)
+    }
+
+    /// Check the prefix for problems that would break path conversions, like broken drive
+    /// symlinks or a missing `dosdevices` directory.
+    ///
+    /// Such problems don't cause errors when the drive mappings are read: the drives are just
+    /// not mapped, and conversions fail with [`WinePathError::NoDrive`]. Returns an empty list
+    /// if no problems were found.
     ///
     /// ```rust,no_run
     /// use winepath::WineConfig;
     /// let config = WineConfig::from_env().unwrap();
-    /// let path = config.to_wine_path("/home/username/.wine/drive_c/Program Files/CoolApp/start.exe").unwrap();
-    /// assert_eq!(path.to_string(), r"c:\Program Files\CoolApp\start.exe");
-    /// let path = config.to_wine_path("/home/username/some-path/some-file").unwrap();
-    /// assert_eq!(path.to_string(), r"z:\home\username\some-path\some-file");
+    /// for problem in config.diagnose() {
+    ///     eprintln!("warning: {}", problem);
+    /// }
     /// ```
-    #[inline]
-    pub fn to_wine_path(&self, path: impl AsRef<NativePath>) -> Result<WinePath, WinePathError> {
-        let native = path.as_ref();
-        self.to_wine_path_inner(native).map(WinePath)
+    pub fn diagnose(&self) -> Vec<Diagnostic> {
+        diagnose::diagnose(&self.prefix)
     }
 
-    /// Convert a Wine path to a native file path.
+    /// Get the directory that the wineserver of the prefix keeps its socket and lock file in,
+    /// like `/tmp/.wine-1000/server-803-5f2a1c`.
+    ///
+    /// The directory name is computed from the device and inode numbers of the prefix directory,
+    /// like Wine does. It only exists while a wineserver is running, or if one was not shut down
+    /// cleanly. Returns `None` if the prefix directory does not exist.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let socket = config.wineserver_dir().unwrap().join("socket");
+    /// ```
+    #[cfg(unix)]
+    pub fn wineserver_dir(&self) -> Option<PathBuf> {
+        prefix_info::wineserver_dir(&self.prefix)
+    }
+
+    /// Check if a wineserver is running for the prefix, e.g. to warn before changing the drive
+    /// mappings while programs are running.
+    ///
+    /// This connects to the socket in [`WineConfig::wineserver_dir`]. A leftover socket from a
+    /// wineserver that crashed does not count.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// if config.is_running() {
+    ///     eprintln!("close all Windows programs first");
+    /// }
+    /// ```
+    #[cfg(unix)]
+    pub fn is_running(&self) -> bool {
+        self.wineserver_dir()
+            .is_some_and(|dir| std::os::unix::net::UnixStream::connect(dir.join("socket")).is_ok())
+    }
+
+    /// Find a special folder in the user profile, like the desktop or the documents folder.
+    ///
+    /// The path is read from the `Shell Folders` key in the `user.reg` file of the prefix, so it
+    /// is the same folder that Windows programs use. Returns the wine path and the native path,
+    /// or `None` if the folder is not in the registry or not on a mapped drive.
+    ///
+    /// ```rust,no_run
+    /// use winepath::{KnownFolder, WineConfig};
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let (wine_path, native_path) = config.known_folder(KnownFolder::Documents).unwrap();
+    /// assert_eq!(wine_path.to_string(), r"C:\users\username\Documents");
+    /// assert_eq!(native_path, PathBuf::from("/home/username/.wine/drive_c/users/username/Documents"));
+    /// ```
+    pub fn known_folder(&self, folder: KnownFolder) -> Option<(WinePath, PathBuf)> {
+        let registry = RegistryFile::read(&self.prefix, "user.reg")?;
+        let wine_path = registry
+            .get(known_folder::SHELL_FOLDERS_KEY, folder.registry_name())?
+            .as_str()?;
+        let native_path = self.to_native_path(wine_path).ok()?;
+        Some((WinePath::from(wine_path), native_path))
+    }
+
+    /// Find the profile directory of the current user, like `C:\users\username`.
+    ///
+    /// The profile is looked up in the `ProfileList` registry key in `system.reg`. If it isn't
+    /// there, the unix user name is used, like Wine does when it creates the profile. Returns the
+    /// wine path and the native path.
     ///
     /// ```rust,no_run
     /// use winepath::WineConfig;
     /// use std::path::PathBuf;
     /// let config = WineConfig::from_env().unwrap();
-    /// let path = config.to_native_path(r"c:\Program Files\CoolApp\start.exe").unwrap();
-    /// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c/Program Files/CoolApp/start.exe"));
-    /// let path = config.to_native_path(r"z:\home\username\some-path\some-file").unwrap();
-    /// assert_eq!(path, PathBuf::from("/home/username/some-path/some-file"));
+    /// let (wine_path, native_path) = config.user_profile().unwrap();
+    /// assert_eq!(wine_path.to_string(), r"C:\users\username");
+    /// assert_eq!(native_path, PathBuf::from("/home/username/.wine/drive_c/users/username"));
     /// ```
-    #[inline]
-    pub fn to_native_path(&self, path: impl Into<WinePath>) -> Result<PathBuf, WinePathError> {
-        let wine_path = path.into();
-        self.to_native_path_inner(wine_path.0.as_ref())
+    pub fn user_profile(&self) -> Option<(WinePath, PathBuf)> {
+        let from_registry = RegistryFile::read(&self.prefix, "system.reg").and_then(|registry| {
+            registry.keys.iter().find_map(|key| {
+                let sid = strip_prefix_ignore_ascii_case(&key.path, PROFILE_LIST_KEY)?;
+                if !sid.starts_with(r"\S-1-5-21-") {
+                    return None;
+                }
+                key.get("ProfileImagePath")?.as_str().map(WinePath::from)
+            })
+        });
+        let wine_path = from_registry.or_else(|| {
+            let user = std::env::var("USER").ok()?;
+            Some(WinePath(format!(r"C:\users\{}", user)))
+        })?;
+        let native_path = self.to_native_path(wine_path.clone()).ok()?;
+        Some((wine_path, native_path))
+    }
+
+    /// List the user profiles in the prefix, as wine paths and native paths.
+    ///
+    /// These are the directories in `C:\users`, except for the shared `Public`, `Default`,
+    /// `All Users` and `Default User` directories.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// for (wine_path, _) in config.users() {
+    ///     println!("{}", wine_path.file_name().unwrap());
+    /// }
+    /// ```
+    pub fn users(&self) -> Vec<(WinePath, PathBuf)> {
+        let users_dir = WinePath::from(r"C:\users");
+        let entries = match self.to_native_path(users_dir.clone()) {
+            Ok(native) => native.read_dir(),
+            Err(_) => return vec![],
+        };
+        let entries = match entries {
+            Ok(entries) => entries,
+            Err(_) => return vec![],
+        };
+        let mut users: Vec<(WinePath, PathBuf)> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let is_shared = ["Public", "Default", "All Users", "Default User"]
+                    .iter()
+                    .any(|shared| name.eq_ignore_ascii_case(shared));
+                if is_shared {
+                    return None;
+                }
+                Some((users_dir.join(&name), entry.path()))
+            })
+            .collect();
+        users.sort();
+        users
+    }
+
+    /// Get a snapshot of the drive letter → native path mappings.
+    ///
+    /// The first call reads them from the `dosdevices` directory of the prefix. The snapshot is
+    /// not affected by later refreshes.
+    pub fn drive_map(&self) -> Arc<DriveMap> {
+        let cached = self
+            .drive_map
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        match cached {
+            Some(drive_map) => drive_map,
+            None => Arc::clone(
+                self.write_drive_map()
+                    .get_or_insert_with(|| Arc::new(self.scan_drive_map())),
+            ),
+        }
+    }
+
+    /// Lock the drive mappings for writing. The lock can't be poisoned in a meaningful way: the
+    /// mappings are only ever replaced as a whole.
+    fn write_drive_map(&self) -> std::sync::RwLockWriteGuard<'_, Option<Arc<DriveMap>>> {
+        self.drive_map
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Read the drive mappings from the prefix, with the default mappings if they are enabled
+    /// and the prefix has no `dosdevices` directory.
+    fn scan_drive_map(&self) -> DriveMap {
+        let mut drive_map = DriveMap::from_prefix(&self.prefix);
+        if self.default_mappings && !self.prefix.join("dosdevices").is_dir() {
+            debug_event!("no dosdevices directory, using the default drive mappings");
+            drive_map.fill_defaults(&self.prefix, None);
+        }
+        drive_map
+    }
+
+    /// Iterate over the mapped drives and their native paths, in alphabetical order.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// for (letter, path) in config.drives() {
+    ///     println!("{}: {}", letter.to_ascii_uppercase(), path.display());
+    /// }
+    /// ```
+    pub fn drives(&self) -> impl Iterator<Item = (char, PathBuf)> {
+        let drives: Vec<(char, PathBuf)> = self
+            .drive_map()
+            .iter()
+            .map(|(letter, path)| (letter, path.to_path_buf()))
+            .collect();
+        drives.into_iter()
+    }
+
+    /// Get the native path that a drive is mapped to, e.g. where `C:` is on disk.
+    ///
+    /// The drive letter is case-insensitive.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::Path;
+    /// let config = WineConfig::from_env().unwrap();
+    /// assert_eq!(config.native_root('C').as_deref(), Some(Path::new("/home/username/.wine/drive_c")));
+    /// ```
+    pub fn native_root(&self, drive_letter: char) -> Option<PathBuf> {
+        self.drive_map().get(drive_letter).map(Path::to_path_buf)
+    }
+
+    /// Get information about a mapped drive, like its volume label and serial number.
+    ///
+    /// The drive type is detected when the drive mappings are read. Call [`WineConfig::refresh`]
+    /// to pick up changes.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let info = config.drive_info('d').unwrap();
+    /// println!("{}: {}", info.letter, info.label.as_deref().unwrap_or("(no label)"));
+    /// ```
+    pub fn drive_info(&self, drive_letter: char) -> Option<DriveInfo> {
+        let target = self.native_root(drive_letter)?;
+        Some(DriveInfo {
+            letter: drive_letter.to_ascii_lowercase(),
+            drive_type: self.drive_map().drive_type(drive_letter),
+            label: drive_info::read_label(&target),
+            serial: drive_info::read_serial(&target),
+            target,
+        })
+    }
+
+    /// Set the volume label of a drive, by writing the `.windows-label` file in the drive root.
+    ///
+    /// Some copy-protected programs check for a specific volume label.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// config.set_drive_label('d', "COOLGAME_DISC1").unwrap();
+    /// assert_eq!(config.drive_info('d').unwrap().label.as_deref(), Some("COOLGAME_DISC1"));
+    /// ```
+    ///
+    /// Returns an [`std::io::ErrorKind::NotFound`] error if the drive is not mapped.
+    pub fn set_drive_label(&self, drive_letter: char, label: &str) -> std::io::Result<()> {
+        drive_info::write_label(&self.mapped_root(drive_letter)?, label)
+    }
+
+    /// Set the volume serial number of a drive, by writing the `.windows-serial` file in the
+    /// drive root.
+    ///
+    /// Returns an [`std::io::ErrorKind::NotFound`] error if the drive is not mapped.
+    pub fn set_drive_serial(&self, drive_letter: char, serial: u32) -> std::io::Result<()> {
+        drive_info::write_serial(&self.mapped_root(drive_letter)?, serial)
+    }
+
+    /// Get the native root of a drive, or an error if it is not mapped.
+    fn mapped_root(&self, drive_letter: char) -> std::io::Result<PathBuf> {
+        self.native_root(drive_letter)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "drive is not mapped"))
+    }
+
+    /// Find the drive that a native path is on, and the native path of its root.
+    ///
+    /// This picks the same drive as [`WineConfig::to_wine_path`], without building the wine path.
+    /// Returns `None` if the path is not on any drive, or only on a UNC share.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::Path;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let (letter, root) = config.drive_for("/home/username/.wine/drive_c/windows").unwrap();
+    /// assert_eq!(letter, 'c');
+    /// assert_eq!(root, Path::new("/home/username/.wine/drive_c"));
+    /// ```
+    pub fn drive_for(&self, path: impl AsRef<NativePath>) -> Option<(char, PathBuf)> {
+        let native = canonicalize(path.as_ref(), self.canonicalize_policy).ok()?;
+        let drive_map = self.drive_map();
+        let letter = self
+            .matching_roots(&drive_map, &native)
+            .into_iter()
+            .find_map(|(root, _)| match root {
+                WineRoot::Drive(letter) => Some(letter),
+                WineRoot::Unc(..) => None,
+            })?;
+        drive_map
+            .get(letter)
+            .map(|root| (letter, root.to_path_buf()))
+    }
+
+    /// Replace the drive letter → native path mappings.
+    ///
+    /// ```rust
+    /// use winepath::{DriveMap, WineConfig};
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_prefix("/home/username/.wine");
+    /// let mut drives = DriveMap::clone(&config.drive_map());
+    /// drives.insert('d', "/home/username/games");
+    /// config.set_drive_map(drives);
+    /// let path = config.to_native_path(r"d:\CoolGame").unwrap();
+    /// assert_eq!(path, PathBuf::from("/home/username/games/CoolGame"));
+    /// ```
+    ///
+    /// This replaces the mappings in all clones of the config.
+    pub fn set_drive_map(&self, drive_map: DriveMap) {
+        *self.write_drive_map() = Some(Arc::new(drive_map));
+    }
+
+    /// Get the policy that [`WineConfig::to_wine_path`] uses to canonicalize native paths.
+    pub fn canonicalize_policy(&self) -> CanonicalizePolicy {
+        self.canonicalize_policy
+    }
+
+    /// Set the policy that [`WineConfig::to_wine_path`] uses to canonicalize native paths.
+    ///
+    /// ```rust
+    /// use winepath::{CanonicalizePolicy, WineConfig};
+    /// let mut config = WineConfig::from_prefix("/home/username/.wine");
+    /// config.set_canonicalize_policy(CanonicalizePolicy::ExistingAncestor);
+    /// assert_eq!(config.canonicalize_policy(), CanonicalizePolicy::ExistingAncestor);
+    /// ```
+    pub fn set_canonicalize_policy(&mut self, policy: CanonicalizePolicy) {
+        self.canonicalize_policy = policy;
+        self.reset_cache();
+    }
+
+    /// Get the options that are used to render wine paths.
+    pub fn format_options(&self) -> &WineFormatOptions {
+        &self.format_options
+    }
+
+    /// Set the options that are used to render wine paths.
+    ///
+    /// ```rust,no_run
+    /// use winepath::{DriveLetterCase, WineConfig, WineFormatOptions};
+    /// let mut config = WineConfig::from_env().unwrap();
+    /// config.set_format_options(WineFormatOptions {
+    ///     drive_letter_case: DriveLetterCase::Upper,
+    ///     ..Default::default()
+    /// });
+    /// let path = config.to_wine_path("/home/username/.wine/drive_c/windows").unwrap();
+    /// assert_eq!(path.to_string(), r"C:\windows");
+    /// ```
+    pub fn set_format_options(&mut self, options: WineFormatOptions) {
+        self.format_options = options;
+        self.reset_cache();
+    }
+
+    /// Check if [`WineConfig::to_native_path`] resolves paths case-insensitively.
+    pub fn case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    /// Make [`WineConfig::to_native_path`] resolve each component case-insensitively against
+    /// the native filesystem, like Wine does.
+    ///
+    /// Then `C:\Windows\System32` converts to `.../drive_c/windows/system32`, the path that
+    /// actually exists. 8.3 short names are expanded too. Components that don't exist keep their
+    /// literal spelling.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let mut config = WineConfig::from_env().unwrap();
+    /// config.set_case_insensitive(true);
+    /// let path = config.to_native_path(r"C:\Windows\System32\New Folder").unwrap();
+    /// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c/windows/system32/New Folder"));
+    /// ```
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.case_insensitive = case_insensitive;
+        self.reset_cache();
+    }
+
+    /// Get the preference that decides which drive to use when a native path is on several
+    /// drives.
+    pub fn drive_preference(&self) -> &DrivePreference {
+        &self.drive_preference
+    }
+
+    /// Set the preference that decides which drive to use when a native path is on several
+    /// drives. By default, the most specific drive wins.
+    ///
+    /// ```rust,no_run
+    /// use winepath::{DrivePreference, WineConfig};
+    /// let mut config = WineConfig::from_env().unwrap();
+    /// config.set_drive_preference(DrivePreference::Priority(vec!['z']));
+    /// let path = config.to_wine_path("/home/username/.wine/drive_c/windows").unwrap();
+    /// assert_eq!(path.to_string(), r"z:\home\username\.wine\drive_c\windows");
+    /// ```
+    pub fn set_drive_preference(&mut self, preference: DrivePreference) {
+        self.drive_preference = preference;
+        self.reset_cache();
+    }
+
+    /// Get the drive that is used for native paths that are not on a more specific drive.
+    pub fn fallback_drive(&self) -> FallbackDrive {
+        self.fallback_drive
+    }
+
+    /// Set the drive that is used for native paths that are not on a more specific drive. By
+    /// default, drives that are mapped to `/` are used, usually `z:`.
+    ///
+    /// ```rust
+    /// use winepath::{FallbackDrive, WineConfig, WinePathErrorKind};
+    /// use std::path::PathBuf;
+    /// let mut config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    ///     ('z', PathBuf::from("/")),
+    /// ]);
+    /// config.set_fallback_drive(FallbackDrive::Drive('y'));
+    /// let path = config.to_wine_path("/etc/hosts").unwrap();
+    /// assert_eq!(path.to_string(), r"y:\etc\hosts");
+    ///
+    /// config.set_fallback_drive(FallbackDrive::Disabled);
+    /// let err = config.to_wine_path("/etc/hosts").unwrap_err();
+    /// assert_eq!(err.kind(), WinePathErrorKind::NoDrive);
+    /// let path = config.to_wine_path("/home/username/.wine/drive_c/windows").unwrap();
+    /// assert_eq!(path.to_string(), r"c:\windows");
+    /// ```
+    ///
+    /// Panics if the drive letter of [`FallbackDrive::Drive`] is not an ASCII letter.
+    pub fn set_fallback_drive(&mut self, fallback: FallbackDrive) {
+        self.fallback_drive = match fallback {
+            FallbackDrive::Drive(letter) => {
+                assert!(letter.is_ascii_alphabetic());
+                FallbackDrive::Drive(letter.to_ascii_lowercase())
+            }
+            fallback => fallback,
+        };
+        self.reset_cache();
+    }
+
+    /// Check if default drive mappings are assumed for prefixes without a `dosdevices`
+    /// directory.
+    pub fn default_mappings(&self) -> bool {
+        self.default_mappings
+    }
+
+    /// Assume the drive mappings that `wineboot` creates, `c:` for `drive_c` and `z:` for `/`,
+    /// if the prefix has no `dosdevices` directory yet. Off by default.
+    ///
+    /// Fresh prefixes don't have a `dosdevices` directory until `wineboot` runs for the first
+    /// time, so all conversions would fail. This applies the next time the prefix is scanned,
+    /// see [`WineConfig::refresh`].
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let mut config = WineConfig::from_prefix("/home/username/new-prefix");
+    /// config.set_default_mappings(true);
+    /// let path = config.to_wine_path("/home/username/new-prefix/drive_c/setup.exe").unwrap();
+    /// assert_eq!(path.to_string(), r"c:\setup.exe");
+    /// ```
+    pub fn set_default_mappings(&mut self, default_mappings: bool) {
+        self.default_mappings = default_mappings;
+    }
+
+    /// Get the conversion cache, if it is enabled.
+    pub fn cache(&self) -> Option<&ConversionCache> {
+        self.cache.as_deref()
+    }
+
+    /// Cache up to `capacity` recent results of [`WineConfig::to_wine_path`] and
+    /// [`WineConfig::to_native_path`], or disable the cache if `capacity` is 0. Off by default.
+    ///
+    /// This helps if the same paths are converted over and over. The cache is cleared when the
+    /// drive mappings change, and changing other settings starts a new cache. Changes to the file
+    /// system that affect [canonicalization][CanonicalizePolicy] or
+    /// [case-insensitive lookups][WineConfig::set_case_insensitive] are not noticed; use
+    /// [`ConversionCache::clear`] for those. Relative native paths depend on the current
+    /// directory, so they are not cached.
+    ///
+    /// ```rust
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let mut config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    /// ]);
+    /// config.set_cache_capacity(100);
+    /// for _ in 0..3 {
+    ///     config.to_wine_path("/home/username/.wine/drive_c/windows").unwrap();
+    /// }
+    /// let stats = config.cache().unwrap().stats();
+    /// assert_eq!((stats.hits, stats.misses, stats.len), (2, 1, 1));
+    /// let _ = config.to_wine_path("windows");
+    /// assert_eq!(config.cache().unwrap().stats(), stats);
+    /// config.cache().unwrap().clear();
+    /// assert_eq!(config.cache().unwrap().stats().len, 0);
+    /// ```
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.cache = match capacity {
+            0 => None,
+            capacity => Some(Arc::new(ConversionCache::new(capacity))),
+        };
+    }
+
+    /// Start a new, empty cache after a setting changed, so the results of the old settings are
+    /// not used, and clones with the old settings keep theirs.
+    fn reset_cache(&mut self) {
+        if let Some(cache) = &self.cache {
+            self.cache = Some(Arc::new(ConversionCache::new(cache.capacity())));
+        }
+    }
+
+    /// Resolve a native path case-insensitively, if enabled.
+    fn resolve_case(&self, path: PathBuf) -> PathBuf {
+        if self.case_insensitive {
+            resolve_case_insensitive(path)
+        } else {
+            path
+        }
+    }
+
+    /// Find all roots that `path` is in, preferred first.
+    fn matching_roots<'d, 'p>(
+        &self,
+        drive_map: &'d DriveMap,
+        path: &'p NativePath,
+    ) -> Vec<(WineRoot<'d>, &'p NativePath)> {
+        // The roots are sorted deepest first, so by default the most specific drive is preferred:
+        // `d:` mapped to `/home/me/games` should win over `z:` mapped to `/`, like in Wine. On
+        // ties the first drive letter wins.
+        let mut matches: Vec<(WineRoot<'d>, &'p NativePath)> =
+            self.roots_containing(drive_map, path).collect();
+        // The sort is stable, so roots with the same rank stay in order of depth.
+        if self.drive_preference != DrivePreference::MostSpecific {
+            matches.sort_by_key(|(root, remaining)| self.drive_preference.rank(*root, remaining));
+        }
+
+        let mut roots: Vec<(WineRoot<'d>, &'p NativePath)> = vec![];
+        for (root, remaining) in matches {
+            if !roots.iter().any(|(seen, _)| *seen == root) {
+                roots.push((root, remaining));
+            }
+        }
+        roots
+    }
+
+    /// Iterate over the roots that `path` is in, deepest first, and the rest of the path.
+    fn roots_containing<'a, 'd: 'a, 'p: 'a>(
+        &'a self,
+        drive_map: &'d DriveMap,
+        path: &'p NativePath,
+    ) -> impl Iterator<Item = (WineRoot<'d>, &'p NativePath)> + 'a {
+        drive_map
+            .roots_by_depth()
+            .filter(move |(root, root_path)| !self.is_ignored_root(*root, root_path))
+            .filter_map(move |(root, root_path)| {
+                // Returns `err` if `root_path` is not a parent of `path`.
+                let remaining = path.strip_prefix(root_path).ok()?;
+                trace_event!(%root, root_path = %root_path.display(), "path is on root");
+                Some((root, remaining))
+            })
+    }
+
+    /// Find the preferred root that `path` is in.
+    fn preferred_root<'d, 'p>(
+        &self,
+        drive_map: &'d DriveMap,
+        path: &'p NativePath,
+    ) -> Option<(WineRoot<'d>, &'p NativePath)> {
+        if self.drive_preference == DrivePreference::MostSpecific {
+            // Longest prefix match: the first root is the deepest.
+            return self.roots_containing(drive_map, path).next();
+        }
+        self.matching_roots(drive_map, path).into_iter().next()
+    }
+
+    fn find_drive_root<'p>(
+        &self,
+        path: &'p NativePath,
+    ) -> Result<(String, &'p NativePath), WinePathError> {
+        let drive_map = self.drive_map();
+        if let Some((root, remaining)) = self.preferred_root(&drive_map, path) {
+            return Ok((root.to_string(), remaining));
+        }
+        match (self.unmapped_fallback_drive(), path.strip_prefix("/")) {
+            (Some(letter), Ok(remaining)) => {
+                debug_event!(%letter, "no drive matches, using the fallback drive");
+                Ok((format!("{}:", letter), remaining))
+            }
+            _ => Err(self.no_drive_error(path)),
+        }
+    }
+
+    /// Check if a root that maps the whole file system is ignored because of the
+    /// [`FallbackDrive`] setting.
+    fn is_ignored_root(&self, root: WineRoot<'_>, root_path: &NativePath) -> bool {
+        if root_path != Path::new("/") {
+            return false;
+        }
+        match self.fallback_drive {
+            FallbackDrive::Mapped => false,
+            FallbackDrive::Drive(letter) => root != WineRoot::Drive(letter),
+            FallbackDrive::Disabled => true,
+        }
+    }
+
+    /// Create a [`WinePathError::NoDrive`] error for a native path, listing the roots that could
+    /// have matched.
+    fn no_drive_error(&self, path: &NativePath) -> WinePathError {
+        let mut mapped: Vec<(String, PathBuf)> = vec![];
+        for (root, root_path) in self.drive_map().roots() {
+            if self.is_ignored_root(root, root_path) {
+                continue;
+            }
+            let root = root.to_string();
+            if !mapped.iter().any(|(seen, _)| *seen == root) {
+                mapped.push((root, root_path.to_path_buf()));
+            }
+        }
+        WinePathError::NoDrive {
+            path: path.to_path_buf(),
+            mapped,
+        }
+    }
+
+    /// Get the fallback drive, if it is not mapped to anything else.
+    fn unmapped_fallback_drive(&self) -> Option<char> {
+        match self.fallback_drive {
+            FallbackDrive::Drive(letter) if !self.drive_map().contains(letter) => Some(letter),
+            _ => None,
+        }
+    }
+
+    fn to_wine_path_inner(&self, path: &NativePath, lossy: bool) -> Result<String, WinePathError> {
+        let mut wine_path = String::new();
+        self.to_wine_path_buf(path, lossy, &mut wine_path)
+            .map(|()| wine_path)
+    }
+
+    fn to_wine_path_buf(
+        &self,
+        path: &NativePath,
+        lossy: bool,
+        wine_path: &mut String,
+    ) -> Result<(), WinePathError> {
+        let _span = debug_span!("to_wine_path", path = %path.display());
+        let result = self.find_drive_root(path).and_then(|(root, remaining)| {
+            self.render_wine_path_into(&root, remaining, path, lossy, wine_path)
+        });
+        debug_event!(result = ?result.as_ref().map(|()| &*wine_path), "converted native path");
+        result
+    }
+
+    fn render_wine_path(
+        &self,
+        root: &str,
+        remaining: &NativePath,
+        path: &NativePath,
+        lossy: bool,
+    ) -> Result<String, WinePathError> {
+        let mut wine_path = String::new();
+        self.render_wine_path_into(root, remaining, path, lossy, &mut wine_path)
+            .map(|()| wine_path)
+    }
+
+    /// Render the wine path for `remaining` on `root` into `wine_path`, replacing its contents.
+    fn render_wine_path_into(
+        &self,
+        root: &str,
+        remaining: &NativePath,
+        path: &NativePath,
+        lossy: bool,
+        wine_path: &mut String,
+    ) -> Result<(), WinePathError> {
+        wine_path.clear();
+        if !push_wine_path(wine_path, root, remaining, lossy) {
+            wine_path.clear();
+            return Err(WinePathError::NotUtf8 {
+                path: path.to_path_buf(),
+            });
+        }
+        *wine_path = self.format_options.apply(std::mem::take(wine_path), path);
+        Ok(())
+    }
+
+    fn to_native_path_inner(&self, path: &str) -> Result<PathBuf, WinePathError> {
+        let mut native = PathBuf::new();
+        self.to_native_path_buf(path, &mut native).map(|()| native)
+    }
+
+    fn to_native_path_buf(&self, path: &str, native: &mut PathBuf) -> Result<(), WinePathError> {
+        let _span = debug_span!("to_native_path", path);
+        let result = self.convert_wine_path(path, native);
+        debug_event!(result = ?result.as_ref().map(|()| &*native), "converted wine path");
+        result
+    }
+
+    /// Convert a wine path into `native`, replacing its contents.
+    fn convert_wine_path(&self, path: &str, native: &mut PathBuf) -> Result<(), WinePathError> {
+        // Windows accepts `/` as a separator, except in `\\?\` and `\??\` paths which are passed
+        // through verbatim.
+        if path.contains('/') && !is_verbatim(path) {
+            return self.to_native_path_buf(&path.replace('/', r"\"), native);
+        }
+        if let Some(nt_path) = path
+            .strip_prefix(EXTENDED_PREFIX)
+            .or_else(|| path.strip_prefix(NT_PREFIX))
+        {
+            // `/` is not a separator in verbatim paths, and can't be part of a file name either.
+            // Only the Unix paths of `\\?\unix\` can contain it.
+            if nt_path.contains('/') && strip_prefix_ignore_ascii_case(nt_path, r"unix\").is_none()
+            {
+                return Err(WinePathError::InvalidWinePath {
+                    path: path.to_string(),
+                });
+            }
+            *native = self.nt_to_native_path(nt_path)?;
+            return Ok(());
+        }
+        if let Some(device_path) = path.strip_prefix(DEVICE_PREFIX) {
+            *native = self.device_to_native_path(device_path)?;
+            return Ok(());
+        }
+
+        // TODO resolve the path…maybe?
+        let drive_letter = match path_kind(path) {
+            WinePathKind::Unc => {
+                *native = self.unc_to_native_path(&path[2..])?;
+                return Ok(());
+            }
+            WinePathKind::Absolute { drive } | WinePathKind::DriveRelative { drive } => drive,
+            _ => {
+                return Err(WinePathError::InvalidWinePath {
+                    path: path.to_string(),
+                })
+            }
+        };
+        let full_path = path;
+
+        let drive_map = self.drive_map();
+        let native_root = match drive_map.get(drive_letter) {
+            Some(native_root) => Some(native_root),
+            None if self.unmapped_fallback_drive() == Some(drive_letter.to_ascii_lowercase()) => {
+                Some(Path::new("/"))
+            }
+            None => None,
+        };
+        if let Some(native_root) = native_root {
+            native.as_mut_os_string().clear();
+            native.push(native_root);
+            for part in full_path[2..].split('\\') {
+                native.push(wine_unescape(part).as_ref());
+            }
+            Ok(())
+        } else {
+            Err(WinePathError::UnmappedDrive {
+                letter: drive_letter.to_ascii_lowercase(),
+            })
+        }
+    }
+
+    fn to_native_path_uncached(&self, path: &str) -> Result<PathBuf, WinePathError> {
+        self.to_native_path_inner(path)
+            .map(|native| self.resolve_case(native))
+    }
+
+    /// Convert the part of a `\\?\` or `\??\` path after the prefix to a native path.
+    fn nt_to_native_path(&self, nt_path: &str) -> Result<PathBuf, WinePathError> {
+        if let Some(unc_path) = strip_prefix_ignore_ascii_case(nt_path, r"UNC\") {
+            return self.unc_to_native_path(unc_path);
+        }
+        if let Some(unix_path) = strip_prefix_ignore_ascii_case(nt_path, r"unix\") {
+            return Ok(unix_to_native_path(unix_path));
+        }
+        self.to_native_path_inner(nt_path)
+    }
+
+    /// Convert the part of a `\\.\` path after the prefix to a native path.
+    ///
+    /// Only drive devices (`\\.\C:`) and UNC shares (`\\.\UNC\server\share`) can be converted.
+    fn device_to_native_path(&self, device_path: &str) -> Result<PathBuf, WinePathError> {
+        if let Some(unc_path) = strip_prefix_ignore_ascii_case(device_path, r"UNC\") {
+            return self.unc_to_native_path(unc_path);
+        }
+        let bytes = device_path.as_bytes();
+        let is_drive = bytes.len() >= 2
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && (bytes.len() == 2 || bytes[2] == b'\\');
+        if is_drive {
+            self.to_native_path_inner(device_path)
+        } else {
+            Err(WinePathError::UnsupportedDevice {
+                path: device_path.to_string(),
+            })
+        }
+    }
+
+    /// Convert a `server\share\path` string (without the leading `\\`) to a native path inside
+    /// `dosdevices/unc`.
+    fn unc_to_native_path(&self, unc_path: &str) -> Result<PathBuf, WinePathError> {
+        let mut parts = unc_path.split('\\');
+        let server = parts.next().filter(|server| !server.is_empty());
+        let share = parts.next().filter(|share| !share.is_empty());
+        let (server, share) = server
+            .zip(share)
+            .ok_or_else(|| WinePathError::InvalidWinePath {
+                path: format!(r"\\{}", unc_path),
+            })?;
+
+        let mut path = match self.drive_map().get_unc(server, share) {
+            Some(native_root) => native_root.to_path_buf(),
+            None => self
+                .prefix
+                .join("dosdevices")
+                .join("unc")
+                .join(server)
+                .join(share),
+        };
+        for part in parts {
+            path.push(wine_unescape(part).as_ref());
+        }
+        Ok(path)
+    }
+
+    /// Convert a native file path to a Wine path.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config.to_wine_path("/home/username/.wine/drive_c/Program Files/CoolApp/start.exe").unwrap();
+    /// assert_eq!(path.to_string(), r"c:\Program Files\CoolApp\start.exe");
+    /// let path = config.to_wine_path("/home/username/some-path/some-file").unwrap();
+    /// assert_eq!(path.to_string(), r"z:\home\username\some-path\some-file");
+    /// ```
+    ///
+    /// If the path is not on any drive, [`WineConfig::to_unix_device_path`] can be used instead.
+    ///
+    /// The path is canonicalized according to the [`CanonicalizePolicy`] set with
+    /// [`WineConfig::set_canonicalize_policy`]. By default, it is used as is. The result is
+    /// rendered according to the [`WineFormatOptions`] set with
+    /// [`WineConfig::set_format_options`].
+    ///
+    /// Returns [`WinePathError::NotUtf8`] if the path is not valid UTF-8. Use
+    /// [`WineConfig::to_wine_path_lossy`] to convert such paths anyway.
+    #[inline]
+    pub fn to_wine_path(&self, path: impl AsRef<NativePath>) -> Result<WinePath, WinePathError> {
+        let path = path.as_ref();
+        let cache = match &self.cache {
+            // Relative paths are resolved against the current directory, which may change.
+            Some(cache) if path.is_absolute() => cache,
+            _ => return self.to_wine_path_with(path, self.canonicalize_policy),
+        };
+        let drive_map = self.drive_map();
+        let key = CacheKey::Native(path.to_path_buf());
+        if let Some(CacheValue::Wine(wine_path)) = cache.get(&drive_map, &key) {
+            return Ok(wine_path);
+        }
+        let wine_path = self.to_wine_path_with(path, self.canonicalize_policy)?;
+        cache.insert(&drive_map, key, CacheValue::Wine(wine_path.clone()));
+        Ok(wine_path)
+    }
+
+    /// Convert a native file path to a Wine path, resolving symlinks and relative paths first.
+    ///
+    /// The path does not need to exist: the deepest existing ancestor is canonicalized, and the
+    /// remaining components are appended. This is useful to find out where a file *will* be.
+    /// This is the same as [`CanonicalizePolicy::ExistingAncestor`].
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// std::env::set_current_dir("/home/username/.wine/drive_c").unwrap();
+    /// let path = config.to_wine_path_resolved("output/file.txt").unwrap();
+    /// assert_eq!(path.to_string(), r"c:\output\file.txt");
+    /// ```
+    pub fn to_wine_path_resolved(
+        &self,
+        path: impl AsRef<NativePath>,
+    ) -> Result<WinePath, WinePathError> {
+        self.to_wine_path_with(path, CanonicalizePolicy::ExistingAncestor)
+    }
+
+    /// Convert a native file path to every Wine path that refers to it, preferred first.
+    ///
+    /// A path can be on several drives, e.g. when `z:` is mapped to `/` and `d:` to
+    /// `/home/username`. The first path is the one that [`WineConfig::to_wine_path`] returns.
+    ///
+    /// The order is decided by the [`DrivePreference`]: by default, the most specific drive
+    /// comes first.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let paths = config.all_wine_paths("/home/username/some-file").unwrap();
+    /// let paths: Vec<String> = paths.iter().map(ToString::to_string).collect();
+    /// assert_eq!(paths, vec![r"d:\some-file", r"z:\home\username\some-file"]);
+    /// ```
+    ///
+    /// Returns [`WinePathError::NoDrive`] if the path is not on any drive.
+    pub fn all_wine_paths(
+        &self,
+        path: impl AsRef<NativePath>,
+    ) -> Result<Vec<WinePath>, WinePathError> {
+        let native = canonicalize(path.as_ref(), self.canonicalize_policy)?;
+        let drive_map = self.drive_map();
+        let roots = self.matching_roots(&drive_map, &native);
+        if roots.is_empty() {
+            return Err(self.no_drive_error(&native));
+        }
+        roots
+            .into_iter()
+            .map(|(root, remaining)| {
+                self.render_wine_path(&root.to_string(), remaining, &native, false)
+                    .map(WinePath)
+            })
+            .collect()
+    }
+
+    /// Convert a native file path to a Wine path, canonicalizing it according to `policy`.
+    ///
+    /// This overrides the policy set with [`WineConfig::set_canonicalize_policy`].
+    ///
+    /// ```rust,no_run
+    /// use winepath::{CanonicalizePolicy, WineConfig};
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config
+    ///     .to_wine_path_with("/home/username/./some-path/../some-file", CanonicalizePolicy::Lexical)
+    ///     .unwrap();
+    /// assert_eq!(path.to_string(), r"z:\home\username\some-file");
+    /// ```
+    pub fn to_wine_path_with(
+        &self,
+        path: impl AsRef<NativePath>,
+        policy: CanonicalizePolicy,
+    ) -> Result<WinePath, WinePathError> {
+        let native = canonicalize(path.as_ref(), policy)?;
+        self.to_wine_path_inner(&native, false).map(WinePath)
+    }
+
+    /// Convert a native file path to a Wine path like [`WineConfig::to_wine_path`], and return
+    /// a trace of the conversion: the canonicalized path, the drives that were considered and
+    /// why they were or weren't used, and the result.
+    ///
+    /// This is useful to debug unexpected conversions.
+    ///
+    /// ```rust,no_run
+    /// use winepath::{CandidateOutcome, WineConfig};
+    /// let config = WineConfig::from_env().unwrap();
+    /// let explanation = config.explain("/home/username/.wine/drive_c/windows");
+    /// for candidate in &explanation.candidates {
+    ///     if let CandidateOutcome::Chosen { remaining } = &candidate.outcome {
+    ///         println!("used {} with {}", candidate.root, remaining.display());
+    ///     }
+    /// }
+    /// println!("{}", explanation);
+    /// ```
+    pub fn explain(&self, path: impl AsRef<NativePath>) -> Explanation {
+        explain::explain(self, path.as_ref(), self.canonicalize_policy)
+    }
+
+    /// Convert a native file path to a Wine path, replacing invalid UTF-8 sequences with U+FFFD.
+    ///
+    /// The result can not necessarily be converted back to the original native path.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+    /// let config = WineConfig::from_env().unwrap();
+    /// let native = OsStr::from_bytes(b"/home/username/caf\xe9.txt");
+    /// let path = config.to_wine_path_lossy(native).unwrap();
+    /// assert_eq!(path.to_string(), "z:\\home\\username\\caf\u{fffd}.txt");
+    /// ```
+    pub fn to_wine_path_lossy(
+        &self,
+        path: impl AsRef<NativePath>,
+    ) -> Result<WinePath, WinePathError> {
+        let native = canonicalize(path.as_ref(), self.canonicalize_policy)?;
+        self.to_wine_path_inner(&native, true).map(WinePath)
+    }
+
+    /// Convert a native file path to a Wine path with 8.3 short names, like `GetShortPathName`.
+    ///
+    /// Components that are not valid 8.3 names are replaced by the same hashed short names that
+    /// Wine generates. This is useful for old programs that can't handle long names or spaces.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config.to_short_wine_path("/home/username/.wine/drive_c/Program Files/CoolApp/start.exe").unwrap();
+    /// assert_eq!(path.to_string(), r"c:\PROG~FBU\CoolApp\start.exe");
+    /// ```
+    ///
+    /// Dots and `~` in the long name are replaced by `_` in the hashed short name, so it has at
+    /// most one dot:
+    /// ```rust
+    /// use winepath::{CanonicalizePolicy, WineConfig};
+    /// use std::path::PathBuf;
+    /// let mut config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('z', PathBuf::from("/")),
+    /// ]);
+    /// config.set_canonicalize_policy(CanonicalizePolicy::Never);
+    /// let path = config.to_short_wine_path("/tmp/v1.2.3.tar").unwrap();
+    /// assert_eq!(path.to_string(), r"z:\tmp\V1_2~PSJ.TAR");
+    /// let path = config.to_short_wine_path("/tmp/a~b long.txt").unwrap();
+    /// assert_eq!(path.to_string(), r"z:\tmp\A_B_~GD5.TXT");
+    /// ```
+    pub fn to_short_wine_path(
+        &self,
+        path: impl AsRef<NativePath>,
+    ) -> Result<WinePath, WinePathError> {
+        let long_path = self.to_wine_path(path)?;
+        let (root, rest, _) = split_root(&long_path.0);
+        let parts: Vec<Cow<'_, str>> = rest
+            .split(['\\', '/'])
+            .map(|part| match part {
+                "" | "." | ".." => Cow::Borrowed(part),
+                name => short_name(name),
+            })
+            .collect();
+        Ok(WinePath(format!("{}{}", root, parts.join(r"\"))))
+    }
+
+    /// Expand 8.3 short names in a Wine path, like `GetLongPathName` and `winepath -l`.
+    ///
+    /// Each component is looked up in the native directory, and replaced by the long name of the
+    /// entry it refers to. Returns the long wine path and the native path. Components that can't
+    /// be found are kept as is.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let (wine_path, native_path) = config.to_long_path(r"c:\PROG~FBU\CoolApp\START.EXE").unwrap();
+    /// assert_eq!(wine_path.to_string(), r"c:\Program Files\CoolApp\start.exe");
+    /// assert_eq!(native_path, PathBuf::from("/home/username/.wine/drive_c/Program Files/CoolApp/start.exe"));
+    /// ```
+    pub fn to_long_path(
+        &self,
+        path: impl Into<WinePath>,
+    ) -> Result<(WinePath, PathBuf), WinePathError> {
+        let mut path = path.into().0;
+        if !is_verbatim(&path) {
+            path = path.replace('/', r"\");
+        }
+        let (root, rest, _) = split_root(&path);
+        let mut native_path = self.to_native_path_inner(root)?;
+        let mut wine_path = root.to_string();
+
+        for part in rest.split('\\').filter(|part| !part.is_empty()) {
+            let long_name = match part {
+                "." | ".." => None,
+                _ => find_long_name(&native_path, part),
+            };
+            let wine_name = match &long_name {
+                Some(name) => wine_escape(&name.to_string_lossy()).into_owned(),
+                None => part.to_string(),
+            };
+            match long_name {
+                Some(name) => native_path.push(name),
+                None => native_path.push(wine_unescape(part).as_ref()),
+            }
+            if !wine_path.is_empty() && !wine_path.ends_with('\\') {
+                wine_path.push('\\');
+            }
+            wine_path.push_str(&wine_name);
+        }
+
+        Ok((WinePath(wine_path), native_path))
+    }
+
+    /// Read the Windows environment variables of the prefix from its registry.
+    ///
+    /// This is the environment that a Windows program started in the prefix sees, without the
+    /// variables that Wine copies over from the unix environment. It includes the variables that
+    /// Wine sets itself, like `SystemRoot` and `ProgramFiles`, the system variables, like
+    /// `TEMP` and `PATH`, and the variables of the user, like `APPDATA`. References to other
+    /// variables in the values are expanded.
+    ///
+    /// Variable names are case-insensitive on Windows. Each variable appears only once in the
+    /// map, with the case it was defined with.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let env = config.environment();
+    /// assert_eq!(env["SystemRoot"], r"C:\windows");
+    /// assert_eq!(env["TEMP"], r"C:\users\username\AppData\Local\Temp");
+    /// ```
+    pub fn environment(&self) -> HashMap<String, String> {
+        environment::read_environment(&self.prefix)
+    }
+
+    /// Expand `%VARIABLE%` references in a wine path, like `%SystemRoot%` or `%APPDATA%`.
+    ///
+    /// Variables are read from the registry of the prefix, like a Windows program would see
+    /// them. Variables in `overrides` take precedence. Unknown variables are kept as is.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::collections::HashMap;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config.expand_env_vars(r"%SystemRoot%\system32\drivers", &HashMap::new());
+    /// assert_eq!(path.to_string(), r"C:\windows\system32\drivers");
+    ///
+    /// let mut overrides = HashMap::new();
+    /// overrides.insert("GAMEDIR".to_string(), r"C:\Games\CoolGame".to_string());
+    /// let path = config.expand_env_vars(r"%GameDir%\save", &overrides);
+    /// assert_eq!(path.to_string(), r"C:\Games\CoolGame\save");
+    /// ```
+    pub fn expand_env_vars(
+        &self,
+        path: impl AsRef<str>,
+        overrides: &HashMap<String, String>,
+    ) -> WinePath {
+        let env = environment::read_environment(&self.prefix);
+        let expanded = environment::expand_vars(path.as_ref(), |name| {
+            environment::get_var(overrides, name)
+                .or_else(|| environment::get_var(&env, name))
+                .map(str::to_string)
+        });
+        WinePath(expanded)
+    }
+
+    /// Convert a Wine path with `%VARIABLE%` references to a native file path. See
+    /// [`WineConfig::expand_env_vars`].
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::collections::HashMap;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config.to_native_path_expanded(r"%APPDATA%\CoolApp", &HashMap::new()).unwrap();
+    /// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c/users/username/AppData/Roaming/CoolApp"));
+    /// ```
+    pub fn to_native_path_expanded(
+        &self,
+        path: impl AsRef<str>,
+        overrides: &HashMap<String, String>,
+    ) -> Result<PathBuf, WinePathError> {
+        self.to_native_path(self.expand_env_vars(path, overrides))
+    }
+
+    /// Find a Windows executable, like `where` on Windows.
+    ///
+    /// `name` is searched in the directories in the `PATH` of the prefix's registry environment.
+    /// If it has no extension, or an extension that isn't in `PATHEXT`, the extensions from
+    /// `PATHEXT` are tried in order, so `regedit` finds `regedit.exe`. File names are matched
+    /// case-insensitively, like Wine does. Absolute wine paths are only looked up in their own
+    /// directory. Returns the wine path and the native path of the first match.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let (wine_path, native_path) = config.which("regedit").unwrap();
+    /// assert_eq!(wine_path.to_string(), r"C:\windows\regedit.exe");
+    /// assert_eq!(native_path, PathBuf::from("/home/username/.wine/drive_c/windows/regedit.exe"));
+    /// ```
+    pub fn which(&self, name: impl AsRef<str>) -> Option<(WinePath, PathBuf)> {
+        let name = WinePath::from(name.as_ref());
+        let file_name = name.file_name()?;
+        let env = environment::read_environment(&self.prefix);
+        let path_ext =
+            environment::get_var(&env, "PATHEXT").unwrap_or(environment::DEFAULT_PATHEXT);
+        let extensions: Vec<&str> = path_ext.split(';').filter(|ext| !ext.is_empty()).collect();
+
+        let mut candidates = vec![];
+        let has_known_extension = name.extension().is_some_and(|ext| {
+            extensions
+                .iter()
+                .any(|known| str_eq_ignore_case(known.trim_start_matches('.'), ext))
+        });
+        if name.extension().is_some() {
+            candidates.push(file_name.to_string());
+        }
+        if !has_known_extension {
+            candidates.extend(extensions.iter().map(|ext| format!("{}{}", file_name, ext)));
+        }
+
+        let dirs: Vec<WinePath> = match name.kind() {
+            WinePathKind::Relative => {
+                let path = environment::get_var(&env, "PATH").unwrap_or_default();
+                path.split(';')
+                    .filter(|dir| !dir.is_empty())
+                    .map(|dir| {
+                        let dir = WinePath::from(dir);
+                        match name.parent() {
+                            Some(parent) if !parent.0.is_empty() => dir.join(parent),
+                            _ => dir,
+                        }
+                    })
+                    .collect()
+            }
+            _ => vec![name.parent()?],
+        };
+
+        dirs.into_iter().find_map(|dir| {
+            let native_dir = self.to_native_path_inner(&dir.0).ok()?;
+            candidates.iter().find_map(|candidate| {
+                let native_path = resolve_case_insensitive(native_dir.join(candidate));
+                if !native_path.is_file() {
+                    return None;
+                }
+                let wine_path = dir.join(native_path.file_name()?.to_str()?);
+                Some((wine_path, native_path))
+            })
+        })
+    }
+
+    /// Convert a Wine path to a native file path, after [normalizing][WinePath::normalize] it.
+    ///
+    /// This removes `.` and `..` segments, so the resulting path is clean.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config.to_native_path_normalized(r"c:\windows\..\users\.\username").unwrap();
+    /// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c/users/username"));
+    /// ```
+    pub fn to_native_path_normalized(
+        &self,
+        path: impl Into<WinePath>,
+    ) -> Result<PathBuf, WinePathError> {
+        let wine_path = path.into().normalize();
+        self.to_native_path_inner(wine_path.0.as_ref())
+            .map(|native| self.resolve_case(native))
+    }
+
+    /// Convert a Wine path to a native file path relative to the wine prefix.
+    ///
+    /// The result stays valid when the prefix is moved or mounted elsewhere: join it onto the new
+    /// [`WineConfig::prefix`] to get the native path again. Returns
+    /// [`WinePathError::OutsidePrefix`] if the path is not inside the prefix, e.g. for most `z:`
+    /// paths.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config.to_prefix_relative_path(r"c:\Program Files\CoolApp\start.exe").unwrap();
+    /// assert_eq!(path, PathBuf::from("drive_c/Program Files/CoolApp/start.exe"));
+    /// ```
+    pub fn to_prefix_relative_path(
+        &self,
+        path: impl Into<WinePath>,
+    ) -> Result<PathBuf, WinePathError> {
+        let native = self.to_native_path(path)?;
+        // Drive targets are canonicalized, so the prefix must be too.
+        let canonical_prefix = self.prefix.canonicalize();
+        let prefix = canonical_prefix.as_deref().unwrap_or(&self.prefix);
+        native
+            .strip_prefix(prefix)
+            .or_else(|_| native.strip_prefix(&self.prefix))
+            .map(Path::to_path_buf)
+            .map_err(|_| WinePathError::OutsidePrefix {
+                path: native.clone(),
+            })
+    }
+
+    /// Convert a native file path to a `\\?\unix\` device path.
+    ///
+    /// Wine can access any native path in this form, so this works for paths that are not mapped
+    /// to a drive. `path` should be absolute.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config.to_unix_device_path("/home/username/some-path/some-file").unwrap();
+    /// assert_eq!(path.to_string(), r"\\?\unix\home\username\some-path\some-file");
+    /// ```
+    ///
+    /// Returns [`WinePathError::NotUtf8`] if the path is not valid UTF-8.
+    pub fn to_unix_device_path(
+        &self,
+        path: impl AsRef<NativePath>,
+    ) -> Result<WinePath, WinePathError> {
+        let native = path.as_ref();
+        let relative = native.strip_prefix("/").unwrap_or(native);
+        stringify_path(UNIX_DEVICE_PREFIX, relative, false)
+            .map(WinePath)
+            .ok_or_else(|| WinePathError::NotUtf8 {
+                path: native.to_path_buf(),
+            })
+    }
+
+    /// Convert a Wine path to a native file path.
+    ///
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config.to_native_path(r"c:\Program Files\CoolApp\start.exe").unwrap();
+    /// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c/Program Files/CoolApp/start.exe"));
+    /// let path = config.to_native_path(r"z:\home\username\some-path\some-file").unwrap();
+    /// assert_eq!(path, PathBuf::from("/home/username/some-path/some-file"));
+    /// ```
+    ///
+    /// UNC paths are mapped through the `dosdevices/unc` directory:
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config.to_native_path(r"\\server\share\file.txt").unwrap();
+    /// assert_eq!(path, PathBuf::from("/home/username/.wine/dosdevices/unc/server/share/file.txt"));
+    /// ```
+    ///
+    /// The `\\?\` extended-length prefix and NT-style `\??\` paths are accepted, too:
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config.to_native_path(r"\\?\c:\windows\system32").unwrap();
+    /// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c/windows/system32"));
+    /// let path = config.to_native_path(r"\??\unix\home\username").unwrap();
+    /// assert_eq!(path, PathBuf::from("/home/username"));
+    /// let path = config.to_native_path(r"\\?\unix\home\username").unwrap();
+    /// assert_eq!(path, PathBuf::from("/home/username"));
+    /// ```
+    ///
+    /// Drive devices in the `\\.\` namespace map to the drive root. Other devices, like serial
+    /// ports, return [`WinePathError::UnsupportedDevice`].
+    /// ```rust,no_run
+    /// use winepath::{WineConfig, WinePathErrorKind};
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config.to_native_path(r"\\.\c:").unwrap();
+    /// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c"));
+    /// let err = config.to_native_path(r"\\.\COM3").unwrap_err();
+    /// assert_eq!(err.kind(), WinePathErrorKind::UnsupportedDevice);
+    /// ```
+    ///
+    /// Forward slashes work as separators, like on Windows:
+    /// ```rust,no_run
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_env().unwrap();
+    /// let path = config.to_native_path("c:/Program Files/CoolApp\\start.exe").unwrap();
+    /// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c/Program Files/CoolApp/start.exe"));
+    /// ```
+    ///
+    /// Except in `\\?\` and `\??\` paths, which are taken verbatim. A `/` in those returns
+    /// [`WinePathError::InvalidWinePath`]:
+    /// ```rust
+    /// use winepath::{WineConfig, WinePathErrorKind};
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    ///     ('z', PathBuf::from("/")),
+    /// ]);
+    /// let err = config.to_native_path(r"\\?\c:/windows/system32").unwrap_err();
+    /// assert_eq!(err.kind(), WinePathErrorKind::InvalidWinePath);
+    /// let err = config.to_native_path(r"\??\c:\windows/system32").unwrap_err();
+    /// assert_eq!(err.kind(), WinePathErrorKind::InvalidWinePath);
+    /// ```
+    ///
+    /// Drive-relative paths like `d:report.doc` are resolved against the drive root. Use
+    /// [`WineCwd`] to track the current directory of each drive.
+    ///
+    /// Malformed paths return [`WinePathError::InvalidWinePath`]:
+    /// ```rust
+    /// use winepath::{WineConfig, WinePathError};
+    /// let config = WineConfig::from_prefix("/home/username/.wine");
+    /// let err = config.to_native_path("not a path").unwrap_err();
+    /// assert!(matches!(err, WinePathError::InvalidWinePath { path } if path == "not a path"));
+    /// ```
+    #[inline]
+    pub fn to_native_path(&self, path: impl Into<WinePath>) -> Result<PathBuf, WinePathError> {
+        let wine_path = path.into();
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return self.to_native_path_uncached(&wine_path.0),
+        };
+        let drive_map = self.drive_map();
+        let key = CacheKey::Wine(wine_path.0.clone());
+        if let Some(CacheValue::Native(native)) = cache.get(&drive_map, &key) {
+            return Ok(native);
+        }
+        let native = self.to_native_path_uncached(&wine_path.0)?;
+        cache.insert(&drive_map, key, CacheValue::Native(native.clone()));
+        Ok(native)
+    }
+
+    /// Convert a native file path to a Wine path like [`WineConfig::to_wine_path`], writing it
+    /// into `wine_path` instead of allocating a new string.
+    ///
+    /// The contents of `wine_path` are replaced, and its allocation is reused. This is useful in
+    /// loops that convert many paths one by one. The [conversion cache][WineConfig::cache] is
+    /// not used. On error, `wine_path` is left empty.
+    ///
+    /// ```rust
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    /// ]);
+    /// let mut wine_path = String::new();
+    /// for name in ["windows", "users"] {
+    ///     let native = PathBuf::from("/home/username/.wine/drive_c").join(name);
+    ///     config.to_wine_path_into(native, &mut wine_path).unwrap();
+    ///     assert_eq!(wine_path, format!(r"c:\{}", name));
+    /// }
+    /// ```
+    pub fn to_wine_path_into(
+        &self,
+        path: impl AsRef<NativePath>,
+        wine_path: &mut String,
+    ) -> Result<(), WinePathError> {
+        wine_path.clear();
+        let native = canonicalize(path.as_ref(), self.canonicalize_policy)?;
+        self.to_wine_path_buf(&native, false, wine_path)
+    }
+
+    /// Convert a Wine path to a native file path like [`WineConfig::to_native_path`], writing it
+    /// into `native` instead of allocating a new path.
+    ///
+    /// The contents of `native` are replaced, and its allocation is reused for drive paths like
+    /// `c:\windows`. The [conversion cache][WineConfig::cache] is not used. On error, `native` is
+    /// left empty.
+    ///
+    /// ```rust
+    /// use winepath::WineConfig;
+    /// use std::path::{Path, PathBuf};
+    /// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    /// ]);
+    /// let mut native = PathBuf::new();
+    /// config.to_native_path_into(r"c:\windows", &mut native).unwrap();
+    /// assert_eq!(native, Path::new("/home/username/.wine/drive_c/windows"));
+    /// ```
+    pub fn to_native_path_into(
+        &self,
+        path: impl AsRef<str>,
+        native: &mut PathBuf,
+    ) -> Result<(), WinePathError> {
+        let result = self.to_native_path_buf(path.as_ref(), native);
+        if result.is_err() {
+            native.as_mut_os_string().clear();
+        } else if self.case_insensitive {
+            *native = resolve_case_insensitive(std::mem::take(native));
+        }
+        result
+    }
+
+    /// Convert many native file paths to Wine paths, like [`WineConfig::to_wine_path`].
+    ///
+    /// The results are in the same order as `paths`, with an error for each path that could not
+    /// be converted. All paths are converted with the same drive mappings, even if they are
+    /// refreshed by another thread in the meantime.
+    ///
+    /// ```rust
+    /// use winepath::{WineConfig, WinePathErrorKind};
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    /// ]);
+    /// let results = config.to_wine_paths(&[
+    ///     "/home/username/.wine/drive_c/windows",
+    ///     "/home/username/Documents",
+    /// ]);
+    /// assert_eq!(results[0].as_ref().unwrap().to_string(), r"c:\windows");
+    /// assert_eq!(results[1].as_ref().unwrap_err().kind(), WinePathErrorKind::NoDrive);
+    /// ```
+    pub fn to_wine_paths(
+        &self,
+        paths: &[impl AsRef<NativePath>],
+    ) -> Vec<Result<WinePath, WinePathError>> {
+        let config = self.pinned();
+        paths.iter().map(|path| config.to_wine_path(path)).collect()
+    }
+
+    /// Convert many Wine paths to native file paths, like [`WineConfig::to_native_path`].
+    ///
+    /// The results are in the same order as `paths`, with an error for each path that could not
+    /// be converted. All paths are converted with the same drive mappings, even if they are
+    /// refreshed by another thread in the meantime.
+    ///
+    /// ```rust
+    /// use winepath::{WineConfig, WinePathErrorKind};
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    /// ]);
+    /// let results = config.to_native_paths(&[r"c:\windows", r"q:\setup.exe"]);
+    /// assert_eq!(results[0].as_ref().unwrap(), &PathBuf::from("/home/username/.wine/drive_c/windows"));
+    /// assert_eq!(results[1].as_ref().unwrap_err().kind(), WinePathErrorKind::UnmappedDrive);
+    /// ```
+    pub fn to_native_paths(
+        &self,
+        paths: &[impl AsRef<str>],
+    ) -> Vec<Result<PathBuf, WinePathError>> {
+        let config = self.pinned();
+        paths
+            .iter()
+            .map(|path| config.to_native_path(path.as_ref()))
+            .collect()
+    }
+
+    /// Convert many native file paths to Wine paths in parallel, like
+    /// [`WineConfig::to_wine_paths`].
+    ///
+    /// Only available with the `rayon` feature. The paths are divided over the threads of the
+    /// global [`rayon`] thread pool, so this is worth it for large batches, or if the
+    /// [`CanonicalizePolicy`] reads the file system.
+    ///
+    /// ```rust
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    /// ]);
+    /// let paths: Vec<PathBuf> = (0..1000)
+    ///     .map(|n| PathBuf::from(format!("/home/username/.wine/drive_c/save{}.dat", n)))
+    ///     .collect();
+    /// let results = config.par_to_wine_paths(&paths);
+    /// assert_eq!(results[999].as_ref().unwrap().to_string(), r"c:\save999.dat");
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_to_wine_paths(
+        &self,
+        paths: &[impl AsRef<NativePath> + Sync],
+    ) -> Vec<Result<WinePath, WinePathError>> {
+        use rayon::prelude::*;
+        let config = self.pinned();
+        paths
+            .par_iter()
+            .map(|path| config.to_wine_path(path))
+            .collect()
+    }
+
+    /// Convert many Wine paths to native file paths in parallel, like
+    /// [`WineConfig::to_native_paths`].
+    ///
+    /// Only available with the `rayon` feature. The paths are divided over the threads of the
+    /// global [`rayon`] thread pool, so this is worth it for large batches, or if
+    /// [case-insensitive lookups][WineConfig::set_case_insensitive] are enabled.
+    ///
+    /// ```rust
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    /// ]);
+    /// let paths: Vec<String> = (0..1000).map(|n| format!(r"c:\save{}.dat", n)).collect();
+    /// let results = config.par_to_native_paths(&paths);
+    /// assert_eq!(
+    ///     results[999].as_ref().unwrap(),
+    ///     &PathBuf::from("/home/username/.wine/drive_c/save999.dat"),
+    /// );
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_to_native_paths(
+        &self,
+        paths: &[impl AsRef<str> + Sync],
+    ) -> Vec<Result<PathBuf, WinePathError>> {
+        use rayon::prelude::*;
+        let config = self.pinned();
+        paths
+            .par_iter()
+            .map(|path| config.to_native_path(path.as_ref()))
+            .collect()
+    }
+
+    /// Get a copy of the config that uses a snapshot of the current drive mappings, and does not
+    /// see later refreshes. It shares the conversion cache.
+    fn pinned(&self) -> WineConfig {
+        WineConfig {
+            drive_map: Arc::new(RwLock::new(Some(self.drive_map()))),
+            ..self.clone()
+        }
     }
 }
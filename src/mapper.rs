@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::{NativePath, WineConfig, WineCwd, WinePath, WinePathError};
+
+/// Converts paths between the native and the wine file system.
+///
+/// [`WineConfig`] is the main implementation. Accept an `impl PathMapper` or a
+/// `&dyn PathMapper` instead of a [`WineConfig`] to be able to substitute a mock in tests, wrap
+/// the config in a caching layer, or use a different backend altogether.
+///
+/// ```rust
+/// use winepath::{PathMapper, WineConfig};
+/// use std::path::PathBuf;
+///
+/// fn installer_path(mapper: &impl PathMapper) -> PathBuf {
+///     mapper.to_native(&r"c:\setup.exe".into()).unwrap()
+/// }
+///
+/// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+/// ]);
+/// assert_eq!(installer_path(&config), PathBuf::from("/home/username/.wine/drive_c/setup.exe"));
+/// ```
+pub trait PathMapper {
+    /// Convert a native path to a wine path.
+    fn to_wine(&self, path: &NativePath) -> Result<WinePath, WinePathError>;
+
+    /// Convert a wine path to a native path.
+    fn to_native(&self, path: &WinePath) -> Result<PathBuf, WinePathError>;
+}
+
+/// Uses [`WineConfig::to_wine_path`] and [`WineConfig::to_native_path`].
+impl PathMapper for WineConfig {
+    fn to_wine(&self, path: &NativePath) -> Result<WinePath, WinePathError> {
+        self.to_wine_path(path)
+    }
+
+    fn to_native(&self, path: &WinePath) -> Result<PathBuf, WinePathError> {
+        self.to_native_path(path.clone())
+    }
+}
+
+/// Uses [`WineCwd::to_wine_path`] and [`WineCwd::to_native_path`], so relative paths are
+/// resolved against the current directory.
+impl PathMapper for WineCwd<'_> {
+    fn to_wine(&self, path: &NativePath) -> Result<WinePath, WinePathError> {
+        self.to_wine_path(path)
+    }
+
+    fn to_native(&self, path: &WinePath) -> Result<PathBuf, WinePathError> {
+        self.to_native_path(path.clone())
+    }
+}
+
+macro_rules! forward_path_mapper {
+    ($($ty:ty),*) => {$(
+        impl<T: PathMapper + ?Sized> PathMapper for $ty {
+            fn to_wine(&self, path: &NativePath) -> Result<WinePath, WinePathError> {
+                (**self).to_wine(path)
+            }
+
+            fn to_native(&self, path: &WinePath) -> Result<PathBuf, WinePathError> {
+                (**self).to_native(path)
+            }
+        }
+    )*};
+}
+
+forward_path_mapper!(&T, &mut T, Box<T>, Rc<T>, Arc<T>);
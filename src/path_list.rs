@@ -0,0 +1,97 @@
+//! Conversion of `PATH`-style lists of paths.
+
+use std::env;
+use std::ffi::{OsStr, OsString};
+
+use crate::{WineConfig, WinePathError};
+
+/// Split a `;`-separated Windows path list into its entries, like Windows does for `PATH`.
+///
+/// Double quotes can be used around entries or parts of entries that contain `;`. They are
+/// removed. Empty entries are skipped.
+fn split_wine_path_list(list: &str) -> Vec<String> {
+    let mut entries = vec![];
+    let mut entry = String::new();
+    let mut quoted = false;
+    for c in list.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => entries.push(std::mem::take(&mut entry)),
+            c => entry.push(c),
+        }
+    }
+    entries.push(entry);
+    entries.retain(|entry| !entry.is_empty());
+    entries
+}
+
+impl WineConfig {
+    /// Convert a `;`-separated Windows path list, like the `PATH` variable inside Wine, to a
+    /// `:`-separated native path list. See [`WineConfig::to_native_path`].
+    ///
+    /// Entries can be quoted to include `;`. Empty entries are skipped: on Unix, they would
+    /// mean the current directory. Returns the error of the first entry that can't be converted,
+    /// or [`WinePathError::InvalidWinePath`] with the entry if its native path contains a `:`.
+    ///
+    /// ```rust
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    ///     ('z', PathBuf::from("/")),
+    /// ]);
+    /// let list = config.to_native_path_list(r#"c:\windows;;"z:\opt\my;tools""#).unwrap();
+    /// assert_eq!(list, "/home/username/.wine/drive_c/windows:/opt/my;tools");
+    /// ```
+    pub fn to_native_path_list(&self, list: &str) -> Result<OsString, WinePathError> {
+        let mut native_list = OsString::new();
+        for entry in split_wine_path_list(list) {
+            let native = self.to_native_path(entry.as_str())?;
+            // Fails if the path contains the separator.
+            let native = env::join_paths([&native])
+                .map_err(|_| WinePathError::InvalidWinePath { path: entry })?;
+            if !native_list.is_empty() {
+                native_list.push(":");
+            }
+            native_list.push(native);
+        }
+        Ok(native_list)
+    }
+
+    /// Convert a `:`-separated native path list, like the `PATH` variable, to a `;`-separated
+    /// Windows path list. See [`WineConfig::to_wine_path`].
+    ///
+    /// Empty entries are skipped. Entries that contain `;` are quoted. Returns the error of the
+    /// first entry that can't be converted.
+    ///
+    /// ```rust
+    /// use winepath::WineConfig;
+    /// use std::path::PathBuf;
+    /// let config = WineConfig::from_drive_map("/home/username/.wine", vec![
+    ///     ('c', PathBuf::from("/home/username/.wine/drive_c")),
+    ///     ('z', PathBuf::from("/")),
+    /// ]);
+    /// let list = config.to_wine_path_list("/home/username/.wine/drive_c/windows::/opt/my;tools").unwrap();
+    /// assert_eq!(list, r#"c:\windows;"z:\opt\my;tools""#);
+    /// ```
+    pub fn to_wine_path_list(&self, list: impl AsRef<OsStr>) -> Result<String, WinePathError> {
+        let mut wine_list = String::new();
+        for entry in env::split_paths(list.as_ref()) {
+            if entry.as_os_str().is_empty() {
+                continue;
+            }
+            let wine_path = self.to_wine_path(entry)?;
+            if !wine_list.is_empty() {
+                wine_list.push(';');
+            }
+            if wine_path.as_ref().contains(';') {
+                wine_list.push('"');
+                wine_list.push_str(wine_path.as_ref());
+                wine_list.push('"');
+            } else {
+                wine_list.push_str(wine_path.as_ref());
+            }
+        }
+        Ok(wine_list)
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+#[cfg(unix)]
+use std::path::PathBuf;
+
+use crate::discover::yaml_value;
+use crate::registry::RegistryFile;
+use crate::NativePath;
+
+/// The architecture of a wine prefix, set with `WINEARCH` when it was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WineArch {
+    /// A 32-bit prefix. `system32` holds 32-bit libraries.
+    Win32,
+    /// A 64-bit prefix. `system32` holds 64-bit libraries, and `syswow64` 32-bit ones.
+    Win64,
+}
+
+impl WineArch {
+    /// Read the architecture from the `#arch=` header of a registry file.
+    pub(crate) fn from_registry(registry: &RegistryFile) -> Option<Self> {
+        Self::from_name(registry.option("arch")?)
+    }
+
+    /// Parse a `WINEARCH` value, like `win64`.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "win32" => Some(WineArch::Win32),
+            "win64" => Some(WineArch::Win64),
+            _ => None,
+        }
+    }
+}
+
+/// Read the version of Wine or Proton that last updated a prefix, if it was recorded.
+///
+/// Proton records its version in a `version` file next to the `pfx` directory, like
+/// `1700000000 proton-8.0-5`. Bottles records the runner in `bottle.yml` in the prefix.
+pub(crate) fn read_wine_version(prefix: &NativePath) -> Option<String> {
+    let proton_version = prefix
+        .parent()
+        .filter(|_| prefix.file_name().is_some_and(|name| name == "pfx"))
+        .and_then(|proton_dir| std::fs::read_to_string(proton_dir.join("version")).ok())
+        .and_then(|version| version.split_whitespace().last().map(str::to_string));
+    proton_version.or_else(|| {
+        let bottle = std::fs::read_to_string(prefix.join("bottle.yml")).ok()?;
+        yaml_value(&bottle, None, "Runner")
+    })
+}
+
+/// Compute the directory of the wineserver of a prefix, like Wine does: `/tmp/.wine-<uid>/`,
+/// then `server-<device>-<inode>` of the prefix directory in hexadecimal.
+///
+/// Wine uses the uid of the current user, and refuses to use prefixes owned by other users, so
+/// the owner of the prefix is used here.
+#[cfg(unix)]
+pub(crate) fn wineserver_dir(prefix: &NativePath) -> Option<PathBuf> {
+    let metadata = std::fs::metadata(prefix).ok()?;
+    Some(PathBuf::from(format!(
+        "/tmp/.wine-{}/server-{:x}-{:x}",
+        metadata.uid(),
+        metadata.dev(),
+        metadata.ino()
+    )))
+}
@@ -0,0 +1,93 @@
+use std::borrow::Cow;
+
+/// Characters that `cmd.exe` interprets, and that must be escaped with `^`.
+const CMD_METACHARACTERS: &[char] = &['(', ')', '%', '!', '^', '"', '<', '>', '&', '|'];
+
+/// Quote an argument for a Windows command line, so that `CommandLineToArgvW` and the C runtime
+/// of the program parse it back as a single argument.
+///
+/// Arguments without spaces, tabs or quotes are returned as is. Others are put in double quotes,
+/// with quotes and the backslashes before them escaped.
+///
+/// ```rust
+/// use winepath::quote_arg;
+/// assert_eq!(quote_arg(r"c:\windows"), r"c:\windows");
+/// assert_eq!(quote_arg(r"c:\Program Files\"), r#""c:\Program Files\\""#);
+/// assert_eq!(quote_arg(r#"say "hi""#), r#""say \"hi\"""#);
+/// assert_eq!(quote_arg(""), r#""""#);
+/// ```
+pub fn quote_arg(arg: &str) -> Cow<'_, str> {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '\x0b', '"']) {
+        return Cow::Borrowed(arg);
+    }
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Backslashes before a quote are escapes, so double them, and escape the quote.
+                quoted.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                backslashes = 0;
+            }
+            _ => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes));
+                backslashes = 0;
+            }
+        }
+        if c != '\\' {
+            quoted.push(c);
+        }
+    }
+    // The closing quote follows, so trailing backslashes must be doubled too.
+    quoted.extend(std::iter::repeat_n('\\', backslashes * 2));
+    quoted.push('"');
+    Cow::Owned(quoted)
+}
+
+/// Quote an argument for a command line that is run by `cmd.exe`, like with `cmd /c` or
+/// `start`.
+///
+/// The argument is quoted like [`quote_arg`], and then characters that `cmd.exe` interprets,
+/// like `&`, `%` and quotes, are escaped with `^`. `cmd.exe` removes the carets, and passes the
+/// quoted argument on to the program.
+///
+/// ```rust
+/// use winepath::quote_cmd_arg;
+/// assert_eq!(quote_cmd_arg(r"c:\games\Tom&Jerry"), r"c:\games\Tom^&Jerry");
+/// assert_eq!(quote_cmd_arg(r"c:\Program Files (x86)"), r#"^"c:\Program Files ^(x86^)^""#);
+/// ```
+pub fn quote_cmd_arg(arg: &str) -> Cow<'_, str> {
+    let quoted = quote_arg(arg);
+    if !quoted.contains(CMD_METACHARACTERS) {
+        return quoted;
+    }
+    let mut escaped = String::with_capacity(quoted.len() * 2);
+    for c in quoted.chars() {
+        if CMD_METACHARACTERS.contains(&c) {
+            escaped.push('^');
+        }
+        escaped.push(c);
+    }
+    Cow::Owned(escaped)
+}
+
+/// Build a Windows command line from a program and its arguments, quoting each one with
+/// [`quote_arg`].
+///
+/// ```rust
+/// use winepath::join_args;
+/// let command_line = join_args([r"c:\Program Files\Game\game.exe", "-save", r"z:\home\me\save 1.dat"]);
+/// assert_eq!(command_line, r#""c:\Program Files\Game\game.exe" -save "z:\home\me\save 1.dat""#);
+/// ```
+pub fn join_args<S: AsRef<str>>(args: impl IntoIterator<Item = S>) -> String {
+    let mut command_line = String::new();
+    for (index, arg) in args.into_iter().enumerate() {
+        if index > 0 {
+            command_line.push(' ');
+        }
+        command_line.push_str(&quote_arg(arg.as_ref()));
+    }
+    command_line
+}
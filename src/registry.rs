@@ -0,0 +1,186 @@
+//! A minimal reader for the `.reg` files that Wine keeps in the prefix.
+
+use std::borrow::Cow;
+
+use crate::wine_path::str_eq_ignore_case;
+use crate::NativePath;
+
+/// A registry value. Only the types this crate needs are parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RegistryValue {
+    /// `"value"`
+    String(String),
+    /// `str(2):"value"`, a string with `%VARIABLES%` in it.
+    ExpandString(String),
+    /// `hex:2f,00`
+    Binary(Vec<u8>),
+    /// Binary data and other types.
+    Other,
+}
+
+impl RegistryValue {
+    /// Get the value as a string, if it is one.
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            RegistryValue::String(value) | RegistryValue::ExpandString(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// A registry key with its values.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RegistryKey {
+    /// Path of the key, with `\` separators.
+    pub(crate) path: String,
+    /// The values of the key. The default value is named `@`.
+    pub(crate) values: Vec<(String, RegistryValue)>,
+}
+
+impl RegistryKey {
+    /// Get a value by name, case-insensitively.
+    pub(crate) fn get(&self, name: &str) -> Option<&RegistryValue> {
+        self.values
+            .iter()
+            .find(|(value_name, _)| str_eq_ignore_case(value_name, name))
+            .map(|(_, value)| value)
+    }
+}
+
+/// A parsed `.reg` file, like `system.reg` or `user.reg`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RegistryFile {
+    /// Header options, like `#arch=win64`.
+    pub(crate) options: Vec<(String, String)>,
+    pub(crate) keys: Vec<RegistryKey>,
+}
+
+impl RegistryFile {
+    /// Read and parse a `.reg` file in the prefix. Returns `None` if it can't be read.
+    pub(crate) fn read(prefix: &NativePath, name: &str) -> Option<Self> {
+        let contents = std::fs::read(prefix.join(name)).ok()?;
+        Some(Self::parse(&String::from_utf8_lossy(&contents)))
+    }
+
+    /// Parse the contents of a `.reg` file.
+    pub(crate) fn parse(contents: &str) -> Self {
+        let mut file = Self::default();
+        let mut key: Option<RegistryKey> = None;
+        let mut lines = contents.lines();
+        while let Some(first_line) = lines.next() {
+            // Long values are continued on the next line after a `\`.
+            let mut line = Cow::Borrowed(first_line);
+            while line.ends_with('\\') && !line.starts_with('[') {
+                let next = match lines.next() {
+                    Some(next) => next,
+                    None => break,
+                };
+                let mut joined = line.into_owned();
+                joined.pop();
+                joined.push_str(next.trim_start());
+                line = Cow::Owned(joined);
+            }
+
+            if let (Some(option), None) = (line.strip_prefix('#'), &key) {
+                if let Some((name, value)) = option.split_once('=') {
+                    file.options.push((name.to_string(), value.to_string()));
+                }
+            } else if let Some(rest) = line.strip_prefix('[') {
+                file.keys.extend(key.take());
+                let (path, _) = parse_quoted(rest, ']');
+                key = Some(RegistryKey {
+                    path,
+                    values: vec![],
+                });
+            } else if let Some(key) = key.as_mut() {
+                if let Some(value) = parse_value_line(&line) {
+                    key.values.push(value);
+                }
+            }
+        }
+        file.keys.extend(key);
+        file
+    }
+
+    /// Get a key by path, case-insensitively.
+    pub(crate) fn key(&self, path: &str) -> Option<&RegistryKey> {
+        self.keys
+            .iter()
+            .find(|key| str_eq_ignore_case(&key.path, path))
+    }
+
+    /// Get a value from a key.
+    pub(crate) fn get(&self, path: &str, name: &str) -> Option<&RegistryValue> {
+        self.key(path).and_then(|key| key.get(name))
+    }
+
+    /// Get a header option, like `arch`.
+    pub(crate) fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|(option, _)| option == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse an escaped string up to the unescaped `end` character. Returns the string and the rest
+/// of the input after `end`.
+fn parse_quoted(input: &str, end: char) -> (String, &str) {
+    let mut value = String::new();
+    let mut chars = input.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            c if c == end => return (value, &input[index + c.len_utf8()..]),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, '0')) => value.push('\0'),
+                Some((start, 'x')) => {
+                    // Up to 4 hex digits for a UTF-16 code unit.
+                    let digits: String = input[start + 1..]
+                        .chars()
+                        .take(4)
+                        .take_while(char::is_ascii_hexdigit)
+                        .collect();
+                    for _ in 0..digits.len() {
+                        chars.next();
+                    }
+                    let unit = u32::from_str_radix(&digits, 16).ok();
+                    value.extend(unit.and_then(char::from_u32));
+                }
+                Some((_, escaped)) => value.push(escaped),
+                None => {}
+            },
+            c => value.push(c),
+        }
+    }
+    (value, "")
+}
+
+/// Parse a `"name"=value` line.
+fn parse_value_line(line: &str) -> Option<(String, RegistryValue)> {
+    let (name, rest) = if let Some(rest) = line.strip_prefix('@') {
+        ("@".to_string(), rest)
+    } else {
+        parse_quoted(line.strip_prefix('"')?, '"')
+    };
+    let data = rest.strip_prefix('=')?;
+
+    let value = if let Some(string) = data.strip_prefix('"') {
+        RegistryValue::String(parse_quoted(string, '"').0)
+    } else if let Some(string) = data.strip_prefix("str(2):\"") {
+        RegistryValue::ExpandString(parse_quoted(string, '"').0)
+    } else if let Some(hex) = data.strip_prefix("hex:") {
+        let bytes = hex
+            .split(',')
+            .filter(|byte| !byte.is_empty())
+            .map(|byte| u8::from_str_radix(byte.trim(), 16))
+            .collect::<Result<_, _>>()
+            .ok()?;
+        RegistryValue::Binary(bytes)
+    } else {
+        RegistryValue::Other
+    };
+    Some((name, value))
+}
@@ -0,0 +1,114 @@
+use std::borrow::Cow;
+
+use crate::wine_path::{is_reserved_name, split_root};
+use crate::WinePath;
+
+/// Start of the private use range that Wine maps characters to when they are not allowed in
+/// Windows file names.
+const WINE_ESCAPE_BASE: u32 = 0xf000;
+
+/// Characters that Wine escapes in native file names.
+const WINE_ESCAPED_CHARACTERS: &[char] = &['*', ':', '<', '>', '?', '\\', '|', '"'];
+
+/// Map a character that is not allowed in Windows file names to the private use range, like Wine.
+fn wine_escape_char(c: char) -> char {
+    if WINE_ESCAPED_CHARACTERS.contains(&c) {
+        char::from_u32(WINE_ESCAPE_BASE + c as u32).unwrap()
+    } else {
+        c
+    }
+}
+
+/// Undo [`wine_escape_char`].
+fn wine_unescape_char(c: char) -> char {
+    (c as u32)
+        .checked_sub(WINE_ESCAPE_BASE)
+        .and_then(char::from_u32)
+        .filter(|unescaped| WINE_ESCAPED_CHARACTERS.contains(unescaped))
+        .unwrap_or(c)
+}
+
+/// Escape a native file name like Wine does when listing a directory.
+pub(crate) fn wine_escape(name: &str) -> Cow<'_, str> {
+    if name.contains(WINE_ESCAPED_CHARACTERS) {
+        Cow::Owned(name.chars().map(wine_escape_char).collect())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Turn a file name as seen by Wine applications back into the native file name.
+pub(crate) fn wine_unescape(name: &str) -> Cow<'_, str> {
+    if name.chars().any(|c| wine_unescape_char(c) != c) {
+        Cow::Owned(name.chars().map(wine_unescape_char).collect())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Check if a character is not allowed in Windows file names.
+fn is_illegal_char(c: char) -> bool {
+    c.is_ascii_control() || matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*' | '\\' | '/')
+}
+
+/// How [`sanitize_filename`] should handle characters that are not allowed on Windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanitizeStrategy {
+    /// Replace illegal characters and trailing dots and spaces by the given character, and
+    /// prefix reserved device names with it.
+    Replace(char),
+    /// Map illegal characters to the private use range, like Wine does for native file names.
+    ///
+    /// Wine applications will see the same names as when Wine lists the directory.
+    WineEscape,
+}
+
+/// Make a native file name safe to use on Windows.
+///
+/// ```rust
+/// use winepath::{sanitize_filename, SanitizeStrategy};
+/// assert_eq!(sanitize_filename("a:b?.txt", SanitizeStrategy::Replace('_')), "a_b_.txt");
+/// assert_eq!(sanitize_filename("con.txt", SanitizeStrategy::Replace('_')), "_con.txt");
+/// assert_eq!(sanitize_filename("a:b", SanitizeStrategy::WineEscape), "a\u{f03a}b");
+/// ```
+pub fn sanitize_filename(name: &str, strategy: SanitizeStrategy) -> String {
+    match strategy {
+        SanitizeStrategy::WineEscape => wine_escape(name).into_owned(),
+        SanitizeStrategy::Replace(replacement) => {
+            let mut sanitized: String = name
+                .chars()
+                .map(|c| if is_illegal_char(c) { replacement } else { c })
+                .collect();
+            let trimmed_len = sanitized.trim_end_matches(['.', ' ']).len();
+            let trailing = sanitized.len() - trimmed_len;
+            sanitized.truncate(trimmed_len);
+            sanitized.extend(std::iter::repeat_n(replacement, trailing));
+            if is_reserved_name(&sanitized) {
+                sanitized.insert(0, replacement);
+            }
+            sanitized
+        }
+    }
+}
+
+/// Make every file name in a wine path safe to use on Windows. See [`sanitize_filename`].
+///
+/// The root of the path is kept as is.
+///
+/// ```rust
+/// use winepath::{sanitize_wine_path, SanitizeStrategy, WinePath};
+/// let path = WinePath::from(r"c:\downloads\what?\a|b.txt");
+/// let path = sanitize_wine_path(&path, SanitizeStrategy::Replace('_'));
+/// assert_eq!(path.to_string(), r"c:\downloads\what_\a_b.txt");
+/// ```
+pub fn sanitize_wine_path(path: &WinePath, strategy: SanitizeStrategy) -> WinePath {
+    let (root, rest, _) = split_root(&path.0);
+    let parts: Vec<String> = rest
+        .split(['\\', '/'])
+        .map(|part| match part {
+            "" | "." | ".." => part.to_string(),
+            name => sanitize_filename(name, strategy),
+        })
+        .collect();
+    WinePath(format!("{}{}", root, parts.join(r"\")))
+}
@@ -0,0 +1,216 @@
+//! 8.3 short file names, generated the same way as Wine.
+use std::{borrow::Cow, convert::TryFrom, ffi::OsString, path::Path};
+
+use crate::sanitize::{wine_escape, wine_unescape};
+use crate::wine_path::str_eq_ignore_case;
+
+/// Characters that Wine replaces by `_` in hashed short names, besides non-ASCII characters.
+const INVALID_DOS_CHARS: &[u16] = &[
+    b'*' as u16,
+    b'?' as u16,
+    b'<' as u16,
+    b'>' as u16,
+    b'|' as u16,
+    b'"' as u16,
+    b'+' as u16,
+    b'=' as u16,
+    b',' as u16,
+    b';' as u16,
+    b'[' as u16,
+    b']' as u16,
+    b' ' as u16,
+    b'~' as u16,
+    b'.' as u16,
+];
+
+/// Characters that are not allowed in 8.3 names, besides non-ASCII characters. A single `.` is
+/// allowed, before the extension.
+const INVALID_8DOT3_CHARS: &[u16] = &[
+    b'*' as u16,
+    b'?' as u16,
+    b'<' as u16,
+    b'>' as u16,
+    b'|' as u16,
+    b'"' as u16,
+    b'+' as u16,
+    b'=' as u16,
+    b',' as u16,
+    b';' as u16,
+    b'[' as u16,
+    b']' as u16,
+    b' ' as u16,
+    b':' as u16,
+    b'/' as u16,
+    b'\\' as u16,
+];
+
+/// Characters used to render the hash in a short name.
+const HASH_CHARS: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ012345";
+
+fn is_invalid_dos_char(c: u16) -> bool {
+    c > 0x7f || INVALID_DOS_CHARS.contains(&c)
+}
+
+fn is_invalid_8dot3_char(c: u16) -> bool {
+    c > 0x7f || INVALID_8DOT3_CHARS.contains(&c)
+}
+
+/// Map a UTF-16 code unit to a single-character case mapping, if there is one.
+fn map_case<I: Iterator<Item = char>>(c: u16, map: impl Fn(char) -> I) -> u16 {
+    let mapped = char::from_u32(u32::from(c)).and_then(|c| {
+        let mut mapped = map(c);
+        match (mapped.next(), mapped.next()) {
+            (Some(mapped), None) => u16::try_from(u32::from(mapped)).ok(),
+            _ => None,
+        }
+    });
+    mapped.unwrap_or(c)
+}
+
+fn to_lower(c: u16) -> u16 {
+    map_case(c, char::to_lowercase)
+}
+
+fn to_upper(c: u16) -> u16 {
+    map_case(c, char::to_uppercase)
+}
+
+/// Check if a file name is a valid 8.3 name, so it doesn't need a separate short name.
+pub(crate) fn is_legal_8dot3(name: &str) -> bool {
+    let name: Vec<u16> = name.encode_utf16().collect();
+    if name.len() > 12 {
+        return false;
+    }
+    // A starting `.` is invalid, except for `.` and `..`.
+    if name.first() == Some(&(b'.' as u16)) {
+        return name.len() == 1 || (name.len() == 2 && name[1] == b'.' as u16);
+    }
+
+    let mut dot = None;
+    for (index, &c) in name.iter().enumerate() {
+        if is_invalid_8dot3_char(c) {
+            return false;
+        }
+        if c == b'.' as u16 {
+            if dot.is_some() {
+                return false;
+            }
+            dot = Some(index);
+        }
+    }
+
+    match dot {
+        None => name.len() <= 8,
+        Some(dot) => dot <= 8 && name.len() - dot > 1 && name.len() - dot < 5,
+    }
+}
+
+/// Compute the hashed 8.3 name for a long file name, like Wine's `hash_short_file_name`.
+fn hash_short_file_name(name: &[u16]) -> String {
+    let mut hash: u16 = 0xbeef;
+    for pair in name.windows(2) {
+        hash = (hash << 3) ^ (hash >> 5) ^ to_lower(pair[0]) ^ (to_lower(pair[1]) << 8);
+    }
+    if let Some(&last) = name.last() {
+        hash = (hash << 3) ^ (hash >> 5) ^ to_lower(last);
+    }
+
+    // The extension starts at the last dot, ignoring dots at the start and end.
+    let ext = if name.len() > 2 {
+        name[1..name.len() - 1]
+            .iter()
+            .rposition(|&c| c == b'.' as u16)
+            .map(|index| index + 1)
+    } else {
+        None
+    };
+
+    let to_dos_char = |c: u16| {
+        if is_invalid_dos_char(c) {
+            b'_' as u16
+        } else {
+            to_upper(c)
+        }
+    };
+
+    let base_len = ext.unwrap_or(name.len()).min(4);
+    let mut short: Vec<u16> = name[..base_len].iter().copied().map(to_dos_char).collect();
+    // Pad to 5 characters with `~`.
+    short.resize(5, b'~' as u16);
+    short.push(u16::from(HASH_CHARS[usize::from((hash >> 10) & 0x1f)]));
+    short.push(u16::from(HASH_CHARS[usize::from((hash >> 5) & 0x1f)]));
+    short.push(u16::from(HASH_CHARS[usize::from(hash & 0x1f)]));
+
+    if let Some(ext) = ext {
+        short.push(b'.' as u16);
+        short.extend(name[ext + 1..].iter().take(3).copied().map(to_dos_char));
+    }
+
+    String::from_utf16_lossy(&short)
+}
+
+/// Get the 8.3 short name for a file name. Names that are valid 8.3 names are returned as is.
+pub(crate) fn short_name(name: &str) -> Cow<'_, str> {
+    if is_legal_8dot3(name) {
+        Cow::Borrowed(name)
+    } else {
+        let name: Vec<u16> = name.encode_utf16().collect();
+        Cow::Owned(hash_short_file_name(&name))
+    }
+}
+
+/// Get the name and extension that Windows uses in numeric-tail short names like `PROGRA~1`: the
+/// long name in upper case, without invalid characters.
+fn numeric_tail_parts(name: &str) -> (String, String) {
+    let clean = |part: &str| -> String {
+        part.chars()
+            .filter(|&c| c.is_ascii() && !is_invalid_8dot3_char(c as u16) && c != '.')
+            .map(|c| c.to_ascii_uppercase())
+            .collect()
+    };
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => (clean(&name[..dot]), clean(&name[dot + 1..])),
+        _ => (clean(name), String::new()),
+    }
+}
+
+/// Find the entry in `dir` that `name` refers to, which may be a long name or a short name.
+///
+/// Short names are matched against the names Wine generates. Windows-style numeric tails, like
+/// `PROGRA~1`, are matched against the entries sharing the same prefix, in sorted order.
+pub(crate) fn find_long_name(dir: &Path, name: &str) -> Option<OsString> {
+    let mut entries: Vec<OsString> = dir
+        .read_dir()
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name())
+        .collect();
+    entries.sort();
+    let entry_names = || {
+        entries
+            .iter()
+            .filter_map(|entry| entry.to_str().map(|utf8| (entry, utf8)))
+    };
+
+    let unescaped = wine_unescape(name);
+    if let Some((entry, _)) = entry_names().find(|(_, entry)| str_eq_ignore_case(entry, &unescaped))
+    {
+        return Some(entry.clone());
+    }
+    if let Some((entry, _)) = entry_names().find(|(_, entry)| {
+        !is_legal_8dot3(entry) && str_eq_ignore_case(&short_name(&wine_escape(entry)), name)
+    }) {
+        return Some(entry.clone());
+    }
+
+    let (short_base, short_ext) = numeric_tail_parts(name);
+    let (prefix, tail) = short_base.rsplit_once('~')?;
+    let index = tail.parse::<usize>().ok()?.checked_sub(1)?;
+    entry_names()
+        .filter(|(_, entry)| {
+            let (base, ext) = numeric_tail_parts(entry);
+            !is_legal_8dot3(entry) && base.starts_with(prefix) && ext.starts_with(&short_ext)
+        })
+        .nth(index)
+        .map(|(entry, _)| entry.clone())
+}
@@ -0,0 +1,275 @@
+//! Finding Proton prefixes in Steam libraries.
+
+use std::path::PathBuf;
+
+use crate::NativePath;
+
+/// Locations of the Steam installation, relative to the home directory.
+const STEAM_ROOTS: &[&str] = &[
+    ".steam/steam",
+    ".steam/root",
+    ".local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/data/Steam",
+];
+
+/// A value in a Valve KeyValues (`.vdf`) file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum VdfValue {
+    String(String),
+    Table(Vec<(String, VdfValue)>),
+}
+
+impl VdfValue {
+    /// Get a value from a table, case-insensitively like Steam.
+    fn get(&self, name: &str) -> Option<&VdfValue> {
+        match self {
+            VdfValue::Table(entries) => entries
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value),
+            VdfValue::String(_) => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            VdfValue::String(value) => Some(value),
+            VdfValue::Table(_) => None,
+        }
+    }
+}
+
+/// A token in a `.vdf` file.
+#[derive(Debug, PartialEq, Eq)]
+enum VdfToken {
+    String(String),
+    Open,
+    Close,
+}
+
+/// Split a `.vdf` file into tokens. Unquoted strings end at whitespace.
+fn tokenize_vdf(input: &str) -> Vec<VdfToken> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => tokens.push(VdfToken::Open),
+            '}' => tokens.push(VdfToken::Close),
+            '/' if chars.peek() == Some(&'/') => {
+                // Comment until the end of the line.
+                for c in &mut chars {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                let mut string = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => string.push('\n'),
+                            Some('t') => string.push('\t'),
+                            Some(escaped) => string.push(escaped),
+                            None => {}
+                        },
+                        c => string.push(c),
+                    }
+                }
+                tokens.push(VdfToken::String(string));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut string = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '{' || c == '}' || c == '"' {
+                        break;
+                    }
+                    string.push(c);
+                    chars.next();
+                }
+                tokens.push(VdfToken::String(string));
+            }
+        }
+    }
+    tokens
+}
+
+/// Parse the entries of a table, up to its closing `}` or the end of the file.
+fn parse_vdf_table(tokens: &mut impl Iterator<Item = VdfToken>) -> Vec<(String, VdfValue)> {
+    let mut entries = vec![];
+    while let Some(token) = tokens.next() {
+        let key = match token {
+            VdfToken::String(key) => key,
+            VdfToken::Close => break,
+            VdfToken::Open => continue,
+        };
+        match tokens.next() {
+            Some(VdfToken::String(value)) => entries.push((key, VdfValue::String(value))),
+            Some(VdfToken::Open) => entries.push((key, VdfValue::Table(parse_vdf_table(tokens)))),
+            Some(VdfToken::Close) | None => break,
+        }
+    }
+    entries
+}
+
+/// Parse a `.vdf` file into a table.
+fn parse_vdf(input: &str) -> VdfValue {
+    VdfValue::Table(parse_vdf_table(&mut tokenize_vdf(input).into_iter()))
+}
+
+/// Find the Steam installations in a home directory.
+pub(crate) fn steam_roots(home: &NativePath) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = vec![];
+    for root in STEAM_ROOTS {
+        let root = home.join(root);
+        // `~/.steam/steam` and `~/.steam/root` are usually symlinks to another root.
+        let root = match root.canonicalize() {
+            Ok(root) if root.join("steamapps").is_dir() => root,
+            _ => continue,
+        };
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+/// A Steam library folder, from `libraryfolders.vdf`.
+#[derive(Debug, Clone)]
+pub(crate) struct SteamLibrary {
+    pub(crate) path: PathBuf,
+    /// App IDs of the games installed in the library. Empty in old versions of the file.
+    pub(crate) apps: Vec<String>,
+}
+
+/// Read the library folders of a Steam installation. The installation itself is always the
+/// first library.
+pub(crate) fn steam_libraries(root: &NativePath) -> Vec<SteamLibrary> {
+    let mut libraries = vec![SteamLibrary {
+        path: root.to_path_buf(),
+        apps: vec![],
+    }];
+    let contents = match std::fs::read_to_string(root.join("steamapps/libraryfolders.vdf")) {
+        Ok(contents) => contents,
+        Err(_) => return libraries,
+    };
+    let vdf = parse_vdf(&contents);
+    let folders = match vdf.get("libraryfolders") {
+        Some(VdfValue::Table(folders)) => folders,
+        _ => return libraries,
+    };
+    for (key, folder) in folders {
+        // Skip other keys, like `contentstatsid`.
+        if key.parse::<u32>().is_err() {
+            continue;
+        }
+        // Old versions of the file map the index to the path directly.
+        let (path, apps) = match folder {
+            VdfValue::String(path) => (path.as_str(), vec![]),
+            VdfValue::Table(_) => {
+                let path = match folder.get("path").and_then(VdfValue::as_str) {
+                    Some(path) => path,
+                    None => continue,
+                };
+                let apps = match folder.get("apps") {
+                    Some(VdfValue::Table(apps)) => {
+                        apps.iter().map(|(app, _)| app.clone()).collect()
+                    }
+                    _ => vec![],
+                };
+                (path, apps)
+            }
+        };
+        let path = PathBuf::from(path);
+        match libraries.iter_mut().find(|library| library.path == path) {
+            Some(library) => library.apps = apps,
+            None => libraries.push(SteamLibrary { path, apps }),
+        }
+    }
+    libraries
+}
+
+/// Find the `compatdata` directory of a game in the Steam libraries in a home directory.
+///
+/// The library that the game is installed in is preferred. Otherwise, the first library that
+/// has a `compatdata` directory for the game is used.
+pub(crate) fn find_compat_data(home: &NativePath, app_id: u32) -> Option<PathBuf> {
+    let app_id = app_id.to_string();
+    let libraries: Vec<SteamLibrary> = steam_roots(home)
+        .iter()
+        .flat_map(|root| steam_libraries(root))
+        .collect();
+    let compat_data = |library: &SteamLibrary| {
+        let path = library.path.join("steamapps/compatdata").join(&app_id);
+        if path.join("pfx").is_dir() {
+            Some(path)
+        } else {
+            None
+        }
+    };
+    libraries
+        .iter()
+        .filter(|library| library.apps.contains(&app_id))
+        .find_map(compat_data)
+        .or_else(|| libraries.iter().find_map(compat_data))
+}
+
+/// A Proton prefix in a Steam library.
+#[cfg(feature = "discover-steam")]
+#[derive(Debug, Clone)]
+pub(crate) struct ProtonPrefix {
+    pub(crate) app_id: String,
+    /// The name of the game, from its app manifest.
+    pub(crate) name: Option<String>,
+    pub(crate) compat_data: PathBuf,
+}
+
+/// Read the name of a game from its `appmanifest_<appid>.acf` file in a library.
+#[cfg(feature = "discover-steam")]
+fn read_app_name(library: &NativePath, app_id: &str) -> Option<String> {
+    let manifest = library.join(format!("steamapps/appmanifest_{}.acf", app_id));
+    let vdf = parse_vdf(&std::fs::read_to_string(manifest).ok()?);
+    Some(vdf.get("AppState")?.get("name")?.as_str()?.to_string())
+}
+
+/// Find all Proton prefixes in the Steam libraries in a home directory.
+#[cfg(feature = "discover-steam")]
+pub(crate) fn proton_prefixes(home: &NativePath) -> Vec<ProtonPrefix> {
+    let libraries: Vec<SteamLibrary> = steam_roots(home)
+        .iter()
+        .flat_map(|root| steam_libraries(root))
+        .collect();
+    let mut prefixes: Vec<ProtonPrefix> = vec![];
+    for library in libraries {
+        let mut entries: Vec<PathBuf> = match library.path.join("steamapps/compatdata").read_dir() {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.join("pfx").is_dir())
+                .collect(),
+            Err(_) => continue,
+        };
+        entries.sort();
+        for compat_data in entries {
+            let app_id = match compat_data.file_name().and_then(|name| name.to_str()) {
+                Some(app_id) => app_id.to_string(),
+                None => continue,
+            };
+            if prefixes
+                .iter()
+                .any(|prefix| prefix.compat_data == compat_data)
+            {
+                continue;
+            }
+            prefixes.push(ProtonPrefix {
+                name: read_app_name(&library.path, &app_id),
+                app_id,
+                compat_data,
+            });
+        }
+    }
+    prefixes
+}
@@ -0,0 +1,119 @@
+//! Helpers for testing code that converts paths, without a Wine install.
+//!
+//! Only available with the `test-util` feature.
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{NativePath, WineConfig};
+
+/// Counter to give every temporary prefix in this process a unique name.
+static PREFIX_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Creates a wine prefix in a temporary directory, with a `dosdevices` directory and drive
+/// symlinks like Wine creates them.
+///
+/// By default, the prefix has a `drive_c` directory mapped to `c:`.
+///
+/// ```rust
+/// use winepath::testing::PrefixBuilder;
+/// let prefix = PrefixBuilder::new()
+///     .drive('z', "/")
+///     .build()
+///     .unwrap();
+/// let config = prefix.config();
+/// let path = config.to_wine_path(prefix.drive_c().join("windows")).unwrap();
+/// assert_eq!(path.to_string(), r"c:\windows");
+/// let path = config.to_wine_path("/etc/hosts").unwrap();
+/// assert_eq!(path.to_string(), r"z:\etc\hosts");
+/// ```
+#[derive(Debug, Clone)]
+pub struct PrefixBuilder {
+    drives: Vec<(char, PathBuf)>,
+}
+
+impl Default for PrefixBuilder {
+    fn default() -> Self {
+        Self {
+            drives: vec![('c', PathBuf::from("../drive_c"))],
+        }
+    }
+}
+
+impl PrefixBuilder {
+    /// Create a builder for a prefix with only the `c:` drive.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map a drive to a native path, replacing an earlier mapping of the drive. Relative paths
+    /// are relative to the `dosdevices` directory, like `../drive_c`.
+    ///
+    /// Panics if `drive_letter` is not an ASCII letter.
+    pub fn drive(mut self, drive_letter: char, target: impl Into<PathBuf>) -> Self {
+        assert!(drive_letter.is_ascii_alphabetic());
+        let drive_letter = drive_letter.to_ascii_lowercase();
+        self.drives.retain(|(letter, _)| *letter != drive_letter);
+        self.drives.push((drive_letter, target.into()));
+        self
+    }
+
+    /// Create the prefix directory.
+    pub fn build(self) -> io::Result<TestPrefix> {
+        let path = loop {
+            let name = format!(
+                "winepath-test-{}-{}",
+                std::process::id(),
+                PREFIX_COUNTER.fetch_add(1, Ordering::Relaxed)
+            );
+            let path = std::env::temp_dir().join(name);
+            match std::fs::create_dir(&path) {
+                Ok(()) => break path,
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        };
+        // Remove the directory again if any of the steps below fail.
+        let mut prefix = TestPrefix { path };
+        prefix.path = prefix.path.canonicalize()?;
+
+        let drives_dir = prefix.path.join("dosdevices");
+        std::fs::create_dir(&drives_dir)?;
+        std::fs::create_dir(prefix.drive_c())?;
+        for (letter, target) in self.drives {
+            std::os::unix::fs::symlink(target, drives_dir.join(format!("{}:", letter)))?;
+        }
+        Ok(prefix)
+    }
+}
+
+/// A wine prefix in a temporary directory, created by [`PrefixBuilder`]. The directory is
+/// removed when this is dropped.
+#[derive(Debug)]
+pub struct TestPrefix {
+    path: PathBuf,
+}
+
+impl TestPrefix {
+    /// Get the path to the prefix.
+    pub fn path(&self) -> &NativePath {
+        &self.path
+    }
+
+    /// Get the path to the `drive_c` directory in the prefix.
+    pub fn drive_c(&self) -> PathBuf {
+        self.path.join("drive_c")
+    }
+
+    /// Create a config for the prefix.
+    pub fn config(&self) -> WineConfig {
+        WineConfig::from_prefix(&self.path)
+    }
+}
+
+impl Drop for TestPrefix {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,116 @@
+//! Automatic drive map refreshing with inotify.
+
+use std::io;
+
+use inotify::{Inotify, WatchMask};
+
+use crate::{NativePath, WineConfig};
+
+/// Changes to a `dosdevices` directory that can affect the drive mappings.
+fn watch_mask() -> WatchMask {
+    WatchMask::CREATE
+        | WatchMask::DELETE
+        | WatchMask::MOVED_FROM
+        | WatchMask::MOVED_TO
+        | WatchMask::DELETE_SELF
+        | WatchMask::MOVE_SELF
+}
+
+/// A [`WineConfig`] that rescans its drive mappings when the `dosdevices` directory changes.
+///
+/// Long-running programs can keep one of these around, so they never convert paths using stale
+/// mappings after the user runs `winecfg` or mounts a disc. Changes are picked up the next time
+/// [`WatchedWineConfig::config`] is called.
+///
+/// Only available with the `watch` feature.
+///
+/// ```rust,no_run
+/// use winepath::WineConfig;
+/// let mut watched = WineConfig::from_env().unwrap().watch().unwrap();
+/// loop {
+///     let path = watched.config().to_wine_path("/home/username/some-file");
+///     // ...
+/// #   break;
+/// }
+/// ```
+#[derive(Debug)]
+pub struct WatchedWineConfig {
+    config: WineConfig,
+    inotify: Inotify,
+}
+
+impl WatchedWineConfig {
+    /// Start watching the `dosdevices` directory of the config's prefix.
+    ///
+    /// Returns an error if the directory does not exist or inotify is not available.
+    pub fn new(config: WineConfig) -> io::Result<Self> {
+        let inotify = Inotify::init()?;
+        let mut watched = Self { config, inotify };
+        watched
+            .inotify
+            .watches()
+            .add(watched.config.prefix().join("dosdevices"), watch_mask())?;
+        watched.watch_unc();
+        Ok(watched)
+    }
+
+    /// Watch the `dosdevices/unc` directory and its server directories, if they exist.
+    fn watch_unc(&mut self) {
+        let unc_dir = self.config.prefix().join("dosdevices").join("unc");
+        let mut watches = self.inotify.watches();
+        if watches.add(&unc_dir, watch_mask()).is_err() {
+            return;
+        }
+        let servers = match unc_dir.read_dir() {
+            Ok(servers) => servers,
+            Err(_) => return,
+        };
+        for server in servers.filter_map(Result::ok) {
+            let _ = watches.add(server.path(), watch_mask());
+        }
+    }
+
+    /// Check if there were any changes since the last call.
+    fn has_changes(&mut self) -> bool {
+        let mut buffer = [0; 4096];
+        let mut changed = false;
+        loop {
+            match self.inotify.read_events(&mut buffer) {
+                Ok(mut events) => changed |= events.next().is_some(),
+                // `WouldBlock` when there are no more events.
+                Err(_) => return changed,
+            }
+        }
+    }
+
+    /// Get the config, after refreshing its drive mappings if they changed.
+    ///
+    /// Mappings set with [`WineConfig::set_drive_map`] are replaced when that happens.
+    pub fn config(&mut self) -> &WineConfig {
+        if self.has_changes() {
+            self.config.refresh();
+            self.watch_unc();
+        }
+        &self.config
+    }
+
+    /// Get the wine prefix that is being watched.
+    pub fn prefix(&self) -> &NativePath {
+        self.config.prefix()
+    }
+
+    /// Stop watching, and return the config.
+    pub fn into_inner(self) -> WineConfig {
+        self.config
+    }
+}
+
+impl WineConfig {
+    /// Watch the `dosdevices` directory of the prefix, refreshing the drive mappings when it
+    /// changes. See [`WatchedWineConfig`].
+    ///
+    /// Only available with the `watch` feature.
+    pub fn watch(self) -> io::Result<WatchedWineConfig> {
+        WatchedWineConfig::new(self)
+    }
+}
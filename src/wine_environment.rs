@@ -0,0 +1,161 @@
+use std::iter::FromIterator;
+use std::path::PathBuf;
+
+use crate::discover::discover_prefixes;
+use crate::{make_absolute, normalize_lexically, NativePath, WineConfig, WinePath, WinePathError};
+
+/// A set of wine prefixes, for programs that work with many prefixes at once, like launchers.
+///
+/// Finds the prefix that a native path belongs to, and converts paths in a specific prefix. The
+/// drive mappings of each prefix are read once, when they are first needed.
+///
+/// ```rust,no_run
+/// use winepath::{WineConfig, WineEnvironment};
+/// use std::path::PathBuf;
+/// let mut env = WineEnvironment::new();
+/// env.insert(WineConfig::from_prefix("/home/username/.wine"));
+/// env.insert(WineConfig::from_prefix("/home/username/Games/cool-game"));
+///
+/// let config = env.owner("/home/username/Games/cool-game/drive_c/game.exe").unwrap();
+/// assert_eq!(config.prefix(), PathBuf::from("/home/username/Games/cool-game"));
+///
+/// let path = env.to_native_path_in("/home/username/.wine", r"C:\windows").unwrap();
+/// assert_eq!(path, PathBuf::from("/home/username/.wine/drive_c/windows"));
+/// ```
+#[derive(Debug, Default)]
+pub struct WineEnvironment {
+    configs: Vec<WineConfig>,
+}
+
+impl WineEnvironment {
+    /// Create an environment without any prefixes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an environment with all prefixes found by
+    /// [`discover_prefixes`][crate::discover::discover_prefixes].
+    pub fn discover() -> Self {
+        discover_prefixes()
+            .into_iter()
+            .map(|prefix| WineConfig::from_prefix(prefix.path))
+            .collect()
+    }
+
+    /// Add a prefix. A config for the same prefix is replaced, and returned.
+    pub fn insert(&mut self, config: WineConfig) -> Option<WineConfig> {
+        match self
+            .configs
+            .iter_mut()
+            .find(|existing| existing.prefix() == config.prefix())
+        {
+            Some(existing) => Some(std::mem::replace(existing, config)),
+            None => {
+                self.configs.push(config);
+                None
+            }
+        }
+    }
+
+    /// Remove a prefix, and return its config.
+    pub fn remove(&mut self, prefix: impl AsRef<NativePath>) -> Option<WineConfig> {
+        let index = self
+            .configs
+            .iter()
+            .position(|config| config.prefix() == prefix.as_ref())?;
+        Some(self.configs.remove(index))
+    }
+
+    /// Get the config of a prefix.
+    pub fn get(&self, prefix: impl AsRef<NativePath>) -> Option<&WineConfig> {
+        self.configs
+            .iter()
+            .find(|config| config.prefix() == prefix.as_ref())
+    }
+
+    /// Get the config of a prefix, to change its settings.
+    pub fn get_mut(&mut self, prefix: impl AsRef<NativePath>) -> Option<&mut WineConfig> {
+        self.configs
+            .iter_mut()
+            .find(|config| config.prefix() == prefix.as_ref())
+    }
+
+    /// Iterate over the configs of all prefixes, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &WineConfig> {
+        self.configs.iter()
+    }
+
+    /// Get the number of prefixes.
+    pub fn len(&self) -> usize {
+        self.configs.len()
+    }
+
+    /// Check if there are no prefixes.
+    pub fn is_empty(&self) -> bool {
+        self.configs.is_empty()
+    }
+
+    /// Find the prefix that a native path is in, like a game executable in `drive_c`.
+    ///
+    /// If prefixes are nested, the innermost one wins. Paths on drives that are mapped outside
+    /// of the prefix directory, like `z:`, don't belong to any prefix.
+    pub fn owner(&self, path: impl AsRef<NativePath>) -> Option<&WineConfig> {
+        let path = normalize_lexically(&make_absolute(path.as_ref()));
+        self.configs
+            .iter()
+            .filter(|config| path.starts_with(config.prefix()))
+            .max_by_key(|config| config.prefix().components().count())
+    }
+
+    /// Convert a native path to a wine path in the prefix it is in. See
+    /// [`WineEnvironment::owner`].
+    ///
+    /// Returns [`WinePathError::PrefixNotFound`] if the path is not in any prefix.
+    pub fn to_wine_path(&self, path: impl AsRef<NativePath>) -> Result<WinePath, WinePathError> {
+        self.owner(&path)
+            .ok_or(WinePathError::PrefixNotFound)?
+            .to_wine_path(path)
+    }
+
+    /// Convert a native path to a wine path in a specific prefix.
+    ///
+    /// Returns [`WinePathError::PrefixNotFound`] if the prefix is not in the environment.
+    pub fn to_wine_path_in(
+        &self,
+        prefix: impl AsRef<NativePath>,
+        path: impl AsRef<NativePath>,
+    ) -> Result<WinePath, WinePathError> {
+        self.get(prefix)
+            .ok_or(WinePathError::PrefixNotFound)?
+            .to_wine_path(path)
+    }
+
+    /// Convert a wine path to a native path in a specific prefix.
+    ///
+    /// Returns [`WinePathError::PrefixNotFound`] if the prefix is not in the environment.
+    pub fn to_native_path_in(
+        &self,
+        prefix: impl AsRef<NativePath>,
+        path: impl Into<WinePath>,
+    ) -> Result<PathBuf, WinePathError> {
+        self.get(prefix)
+            .ok_or(WinePathError::PrefixNotFound)?
+            .to_native_path(path)
+    }
+}
+
+impl FromIterator<WineConfig> for WineEnvironment {
+    fn from_iter<I: IntoIterator<Item = WineConfig>>(iter: I) -> Self {
+        let mut env = Self::new();
+        env.extend(iter);
+        env
+    }
+}
+
+impl Extend<WineConfig> for WineEnvironment {
+    fn extend<I: IntoIterator<Item = WineConfig>>(&mut self, iter: I) {
+        for config in iter {
+            self.insert(config);
+        }
+    }
+}
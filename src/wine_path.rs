@@ -0,0 +1,1036 @@
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+/// Prefix for extended-length paths, like `\\?\C:\foo`.
+pub(crate) const EXTENDED_PREFIX: &str = r"\\?\";
+
+/// Prefix for paths in the DOS device namespace, like `\\.\C:` or `\\.\COM1`.
+pub(crate) const DEVICE_PREFIX: &str = r"\\.\";
+
+/// Prefix for paths in the NT object namespace, like `\??\C:\foo`.
+pub(crate) const NT_PREFIX: &str = r"\??\";
+
+/// Prefix for native paths exposed as devices, like `\\?\unix\home\me`.
+pub(crate) const UNIX_DEVICE_PREFIX: &str = r"\\?\unix";
+
+/// Check if a wine path is passed through verbatim, i.e. it starts with `\\?\` or `\??\`.
+pub(crate) fn is_verbatim(path: &str) -> bool {
+    path.starts_with(EXTENDED_PREFIX) || path.starts_with(NT_PREFIX)
+}
+
+/// Check if `c` is a path separator in `path`.
+///
+/// Both `\` and `/` are separators, except in verbatim paths.
+fn is_separator_in(path: &str, c: char) -> bool {
+    c == '\\' || (c == '/' && !is_verbatim(path))
+}
+
+/// Split a wine path into its root and the rest.
+///
+/// The root is `c:\`, `c:`, `\`, `\\server\share\`, or a prefix like `\\?\c:\` or `\\.\COM1`,
+/// including the trailing separator if there is one. The boolean is true if the path is
+/// absolute, i.e. `..` segments can't go above the root.
+pub(crate) fn split_root(path: &str) -> (&str, &str, bool) {
+    let is_separator = |c: char| is_separator_in(path, c);
+    // Find the end of the segment starting at `start`, including its trailing separator.
+    let segment_end = |start: usize| {
+        path[start..]
+            .find(is_separator)
+            .map_or(path.len(), |index| start + index + 1)
+    };
+
+    let verbatim_prefix_len = [EXTENDED_PREFIX, NT_PREFIX, DEVICE_PREFIX]
+        .iter()
+        .find(|prefix| path.starts_with(*prefix))
+        .map(|prefix| prefix.len());
+    if let Some(prefix_len) = verbatim_prefix_len {
+        let root_len = if strip_prefix_ignore_ascii_case(&path[prefix_len..], r"UNC\").is_some() {
+            segment_end(segment_end(segment_end(prefix_len)))
+        } else {
+            segment_end(prefix_len)
+        };
+        return (&path[..root_len], &path[root_len..], true);
+    }
+
+    let mut chars = path.chars();
+    let (first, second) = (chars.next(), chars.next());
+    if first.is_some_and(is_separator) && second.is_some_and(is_separator) {
+        let root_len = segment_end(segment_end(2));
+        return (&path[..root_len], &path[root_len..], true);
+    }
+    if first.is_some_and(|c| c.is_ascii_alphabetic()) && second == Some(':') {
+        return if path[2..].starts_with(is_separator) {
+            (&path[..3], &path[3..], true)
+        } else {
+            (&path[..2], &path[2..], false)
+        };
+    }
+    if first.is_some_and(is_separator) {
+        return (&path[..1], &path[1..], true);
+    }
+    ("", path, false)
+}
+
+/// Check if a wine path starts with a drive letter and a root, like `C:\`.
+fn is_drive_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\'
+}
+
+/// Split a file name into its stem and extension.
+fn split_file_name(name: &str) -> (&str, Option<&str>) {
+    match name.rfind('.') {
+        None | Some(0) => (name, None),
+        Some(index) => (&name[..index], Some(&name[index + 1..])),
+    }
+}
+
+/// Strip a prefix from a string, ignoring ASCII case.
+pub(crate) fn strip_prefix_ignore_ascii_case<'s>(string: &'s str, prefix: &str) -> Option<&'s str> {
+    if string.len() >= prefix.len()
+        && string.is_char_boundary(prefix.len())
+        && string[..prefix.len()].eq_ignore_ascii_case(prefix)
+    {
+        Some(&string[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+/// Error type for parsing a [`WinePath`] from a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseWinePathError {
+    /// The string is empty.
+    Empty,
+    /// The string contains a NUL character.
+    ContainsNul,
+    /// The string contains a `:` that is not part of a drive letter, like in `1:\foo`.
+    MisplacedColon,
+    /// The string is a UNC path without a server or share name, like `\\server`.
+    IncompleteUnc,
+}
+
+impl Display for ParseWinePathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseWinePathError::Empty => write!(f, "wine path is empty"),
+            ParseWinePathError::ContainsNul => write!(f, "wine path contains a NUL character"),
+            ParseWinePathError::MisplacedColon => {
+                write!(f, "wine path contains a `:` outside of a drive letter")
+            }
+            ParseWinePathError::IncompleteUnc => {
+                write!(f, "UNC path is missing a server or share name")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseWinePathError {}
+
+/// The prefix of a wine path, before the root directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WinePathPrefix<'a> {
+    /// A drive letter, like `C:`.
+    Drive(char),
+    /// A UNC share, like `\\server\share`.
+    Unc(&'a str, &'a str),
+    /// A verbatim drive letter, like `\\?\C:` or `\??\C:`.
+    VerbatimDrive(char),
+    /// A verbatim UNC share, like `\\?\UNC\server\share`.
+    VerbatimUnc(&'a str, &'a str),
+    /// Another verbatim prefix, like `\\?\unix` or `\??\unix`.
+    Verbatim(&'a str),
+    /// A device in the DOS device namespace, like `\\.\COM1`.
+    Device(&'a str),
+}
+
+/// A component of a wine path, see [`WinePath::components`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WineComponent<'a> {
+    /// A drive letter, UNC share or other prefix.
+    Prefix(WinePathPrefix<'a>),
+    /// The root directory, like the `\` in `C:\`.
+    RootDir,
+    /// A `.` component.
+    CurDir,
+    /// A `..` component.
+    ParentDir,
+    /// A file or directory name.
+    Normal(&'a str),
+}
+
+/// The maximum length of a path in most Windows APIs, including the terminating NUL character.
+pub(crate) const MAX_PATH: usize = 260;
+
+/// Characters that are not allowed in Windows file names, besides control characters.
+const ILLEGAL_CHARACTERS: &[char] = &['<', '>', ':', '"', '|', '?', '*'];
+
+/// Device names that can't be used as file names on Windows, with or without an extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Check if a file name is a reserved device name, like `NUL` or `com1.txt`.
+pub(crate) fn is_reserved_name(name: &str) -> bool {
+    let base = name
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(' ');
+    RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(base))
+}
+
+/// A problem found by [`WinePath::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WinePathViolation {
+    /// A component contains a character that is not allowed in Windows file names.
+    IllegalCharacter {
+        /// The offending component.
+        component: String,
+        /// The illegal character.
+        character: char,
+    },
+    /// A component is a reserved device name, like `CON` or `NUL.txt`.
+    ReservedName {
+        /// The offending component.
+        component: String,
+    },
+    /// A component ends with a `.` or a space, which Windows strips.
+    TrailingDotOrSpace {
+        /// The offending component.
+        component: String,
+    },
+    /// The path is longer than `MAX_PATH` (260 characters, including the terminating NUL).
+    TooLong {
+        /// The length of the path in UTF-16 code units.
+        length: usize,
+    },
+}
+
+impl Display for WinePathViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WinePathViolation::IllegalCharacter {
+                component,
+                character,
+            } => write!(
+                f,
+                "{:?} contains illegal character {:?}",
+                component, character
+            ),
+            WinePathViolation::ReservedName { component } => {
+                write!(f, "{:?} is a reserved device name", component)
+            }
+            WinePathViolation::TrailingDotOrSpace { component } => {
+                write!(f, "{:?} ends with a dot or space", component)
+            }
+            WinePathViolation::TooLong { length } => write!(
+                f,
+                "path is {} characters long, the maximum is {}",
+                length,
+                MAX_PATH - 1
+            ),
+        }
+    }
+}
+
+/// The shape of a wine path, see [`WinePath::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WinePathKind {
+    /// An absolute path on a drive, like `C:\windows` or `\\?\C:\windows`.
+    Absolute {
+        /// The drive letter.
+        drive: char,
+    },
+    /// A path relative to the current directory on a drive, like `C:windows`.
+    DriveRelative {
+        /// The drive letter.
+        drive: char,
+    },
+    /// A path relative to the root of the current drive, like `\windows`.
+    Rooted,
+    /// A path relative to the current directory, like `windows\system32`.
+    Relative,
+    /// A UNC path, like `\\server\share\file` or `\\?\UNC\server\share\file`.
+    Unc,
+    /// A device path, like `\\.\COM1` or `\\?\unix\home\me`.
+    Device,
+    /// A path in the NT object namespace, like `\??\C:\windows`.
+    NtNamespace,
+}
+
+/// Classify a wine path. See [`WinePath::kind`].
+pub(crate) fn path_kind(path: &str) -> WinePathKind {
+    if path.starts_with(NT_PREFIX) {
+        return WinePathKind::NtNamespace;
+    }
+    let (root, _, is_absolute) = split_root(path);
+    match parse_prefix(root) {
+        Some(WinePathPrefix::Drive(drive)) if is_absolute => WinePathKind::Absolute { drive },
+        Some(WinePathPrefix::Drive(drive)) => WinePathKind::DriveRelative { drive },
+        Some(WinePathPrefix::VerbatimDrive(drive)) => WinePathKind::Absolute { drive },
+        Some(WinePathPrefix::Unc(..)) | Some(WinePathPrefix::VerbatimUnc(..)) => WinePathKind::Unc,
+        Some(WinePathPrefix::Verbatim(_)) | Some(WinePathPrefix::Device(_)) => WinePathKind::Device,
+        None if root.is_empty() => WinePathKind::Relative,
+        None => WinePathKind::Rooted,
+    }
+}
+
+/// Parse the root returned by [`split_root`] into a prefix.
+fn parse_prefix(root: &str) -> Option<WinePathPrefix<'_>> {
+    let is_separator = |c| is_separator_in(root, c);
+    let parse_drive = |drive: &str| {
+        let mut chars = drive.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(letter), Some(':'), None) if letter.is_ascii_alphabetic() => Some(letter),
+            _ => None,
+        }
+    };
+    fn parse_share<'a>(root: &str, unc_path: &'a str) -> (&'a str, &'a str) {
+        let mut parts = unc_path.split(|c| is_separator_in(root, c));
+        let server = parts.next().unwrap_or_default();
+        let share = parts.next().unwrap_or_default();
+        (server, share)
+    }
+
+    if let Some(verbatim) = root
+        .strip_prefix(EXTENDED_PREFIX)
+        .or_else(|| root.strip_prefix(NT_PREFIX))
+    {
+        if let Some(unc_path) = strip_prefix_ignore_ascii_case(verbatim, r"UNC\") {
+            let (server, share) = parse_share(root, unc_path);
+            return Some(WinePathPrefix::VerbatimUnc(server, share));
+        }
+        let verbatim = verbatim.trim_end_matches(is_separator);
+        return Some(match parse_drive(verbatim) {
+            Some(letter) => WinePathPrefix::VerbatimDrive(letter),
+            None => WinePathPrefix::Verbatim(verbatim),
+        });
+    }
+    if let Some(device) = root.strip_prefix(DEVICE_PREFIX) {
+        return Some(WinePathPrefix::Device(
+            device.trim_end_matches(is_separator),
+        ));
+    }
+    if root.starts_with(is_separator) && root[1..].starts_with(is_separator) {
+        let (server, share) = parse_share(root, &root[2..]);
+        return Some(WinePathPrefix::Unc(server, share));
+    }
+    parse_drive(root.trim_end_matches(is_separator)).map(WinePathPrefix::Drive)
+}
+
+/// Iterate over the components of a wine path. See [`WinePath::components`].
+fn components(path: &str) -> impl Iterator<Item = WineComponent<'_>> {
+    let is_separator = move |c| is_separator_in(path, c);
+    let (root, rest, is_absolute) = split_root(path);
+    let prefix = parse_prefix(root).map(WineComponent::Prefix);
+    let root_dir = if is_absolute {
+        Some(WineComponent::RootDir)
+    } else {
+        None
+    };
+    let starts_with_cur_dir = root.is_empty() && rest.split(is_separator).next() == Some(".");
+    let cur_dir = if starts_with_cur_dir {
+        Some(WineComponent::CurDir)
+    } else {
+        None
+    };
+    let parts = rest.split(is_separator).filter_map(|part| match part {
+        "" | "." => None,
+        ".." => Some(WineComponent::ParentDir),
+        part => Some(WineComponent::Normal(part)),
+    });
+
+    prefix
+        .into_iter()
+        .chain(root_dir)
+        .chain(cur_dir)
+        .chain(parts)
+}
+
+/// Uppercase a character the way Windows does for case-insensitive comparisons.
+///
+/// Windows only maps single characters to single characters, so `ß` stays `ß`.
+fn fold_case(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) => upper,
+        _ => c,
+    }
+}
+
+/// Compare two strings case-insensitively, like Windows does.
+pub(crate) fn str_eq_ignore_case(a: &str, b: &str) -> bool {
+    a.chars().map(fold_case).eq(b.chars().map(fold_case))
+}
+
+/// Compare two strings case-insensitively, like Windows does.
+fn str_cmp_ignore_case(a: &str, b: &str) -> Ordering {
+    a.chars().map(fold_case).cmp(b.chars().map(fold_case))
+}
+
+/// Order of the different kinds of components, when comparing paths.
+fn component_rank(component: WineComponent<'_>) -> u8 {
+    match component {
+        WineComponent::Prefix(WinePathPrefix::Drive(_)) => 0,
+        WineComponent::Prefix(WinePathPrefix::Unc(..)) => 1,
+        WineComponent::Prefix(WinePathPrefix::VerbatimDrive(_)) => 2,
+        WineComponent::Prefix(WinePathPrefix::VerbatimUnc(..)) => 3,
+        WineComponent::Prefix(WinePathPrefix::Verbatim(_)) => 4,
+        WineComponent::Prefix(WinePathPrefix::Device(_)) => 5,
+        WineComponent::RootDir => 6,
+        WineComponent::CurDir => 7,
+        WineComponent::ParentDir => 8,
+        WineComponent::Normal(_) => 9,
+    }
+}
+
+/// Compare two path components case-insensitively, like Windows does.
+fn component_cmp_ignore_case(a: WineComponent<'_>, b: WineComponent<'_>) -> Ordering {
+    use WinePathPrefix::*;
+    match (a, b) {
+        (WineComponent::Prefix(a), WineComponent::Prefix(b)) => match (a, b) {
+            (Drive(a), Drive(b)) | (VerbatimDrive(a), VerbatimDrive(b)) => {
+                fold_case(a).cmp(&fold_case(b))
+            }
+            (Unc(a_server, a_share), Unc(b_server, b_share))
+            | (VerbatimUnc(a_server, a_share), VerbatimUnc(b_server, b_share)) => {
+                str_cmp_ignore_case(a_server, b_server)
+                    .then_with(|| str_cmp_ignore_case(a_share, b_share))
+            }
+            (Verbatim(a), Verbatim(b)) | (Device(a), Device(b)) => str_cmp_ignore_case(a, b),
+            _ => component_rank(WineComponent::Prefix(a))
+                .cmp(&component_rank(WineComponent::Prefix(b))),
+        },
+        (WineComponent::Normal(a), WineComponent::Normal(b)) => str_cmp_ignore_case(a, b),
+        (a, b) => component_rank(a).cmp(&component_rank(b)),
+    }
+}
+
+/// Compare two path components case-insensitively, like Windows does.
+fn component_eq_ignore_case(a: WineComponent<'_>, b: WineComponent<'_>) -> bool {
+    component_cmp_ignore_case(a, b) == Ordering::Equal
+}
+
+/// Hash a path component consistently with [`component_cmp_ignore_case`].
+fn hash_component<H: Hasher>(component: WineComponent<'_>, state: &mut H) {
+    fn hash_str<H: Hasher>(string: &str, state: &mut H) {
+        for c in string.chars().map(fold_case) {
+            c.hash(state);
+        }
+        // Separate consecutive strings, so `ab` + `c` and `a` + `bc` hash differently.
+        state.write_u8(0xff);
+    }
+
+    component_rank(component).hash(state);
+    match component {
+        WineComponent::Prefix(WinePathPrefix::Drive(letter))
+        | WineComponent::Prefix(WinePathPrefix::VerbatimDrive(letter)) => {
+            fold_case(letter).hash(state)
+        }
+        WineComponent::Prefix(WinePathPrefix::Unc(server, share))
+        | WineComponent::Prefix(WinePathPrefix::VerbatimUnc(server, share)) => {
+            hash_str(server, state);
+            hash_str(share, state);
+        }
+        WineComponent::Prefix(WinePathPrefix::Verbatim(name))
+        | WineComponent::Prefix(WinePathPrefix::Device(name))
+        | WineComponent::Normal(name) => hash_str(name, state),
+        WineComponent::RootDir | WineComponent::CurDir | WineComponent::ParentDir => {}
+    }
+}
+
+/// A file path within Wine. Wrapper around a string.
+///
+/// ```rust
+/// use winepath::WinePath;
+/// let wine_path = WinePath(r"C:\windows\system32\ddraw.dll".to_string());
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct WinePath(pub String);
+impl AsRef<str> for WinePath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+impl From<String> for WinePath {
+    fn from(string: String) -> Self {
+        Self(string)
+    }
+}
+impl From<&str> for WinePath {
+    fn from(string: &str) -> Self {
+        Self(string.to_string())
+    }
+}
+impl WinePath {
+    /// Check if this is a path in the NT object namespace, like `\??\C:\windows` or
+    /// `\??\unix\home\me`.
+    ///
+    /// These show up in registry values and Wine debug output. They can be passed to
+    /// [`WineConfig::to_native_path`](crate::WineConfig::to_native_path) as-is.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// assert!(WinePath::from(r"\??\C:\windows\system32").is_nt_path());
+    /// assert!(!WinePath::from(r"C:\windows\system32").is_nt_path());
+    /// ```
+    pub fn is_nt_path(&self) -> bool {
+        self.0.starts_with(NT_PREFIX)
+    }
+
+    /// Classify the shape of this path.
+    ///
+    /// ```rust
+    /// use winepath::{WinePath, WinePathKind};
+    /// assert_eq!(WinePath::from(r"C:\windows").kind(), WinePathKind::Absolute { drive: 'C' });
+    /// assert_eq!(WinePath::from(r"C:windows").kind(), WinePathKind::DriveRelative { drive: 'C' });
+    /// assert_eq!(WinePath::from(r"\windows").kind(), WinePathKind::Rooted);
+    /// assert_eq!(WinePath::from(r"windows").kind(), WinePathKind::Relative);
+    /// assert_eq!(WinePath::from(r"\\server\share").kind(), WinePathKind::Unc);
+    /// assert_eq!(WinePath::from(r"\\.\COM1").kind(), WinePathKind::Device);
+    /// assert_eq!(WinePath::from(r"\??\C:\windows").kind(), WinePathKind::NtNamespace);
+    /// ```
+    pub fn kind(&self) -> WinePathKind {
+        path_kind(&self.0)
+    }
+
+    /// Check if this path can be used on Windows.
+    ///
+    /// This checks each component for characters that are illegal in Windows file names
+    /// (`<>:"|?*` and control characters), reserved device names like `CON` and `NUL`, and
+    /// trailing dots and spaces. Paths other than `\\?\` paths must also be shorter than
+    /// `MAX_PATH`.
+    ///
+    /// ```rust
+    /// use winepath::{WinePath, WinePathViolation};
+    /// assert!(WinePath::from(r"C:\Program Files\CoolApp\start.exe").validate().is_ok());
+    /// let violations = WinePath::from(r"C:\data\aux.txt\what?").validate().unwrap_err();
+    /// assert_eq!(violations, vec![
+    ///     WinePathViolation::ReservedName { component: "aux.txt".to_string() },
+    ///     WinePathViolation::IllegalCharacter { component: "what?".to_string(), character: '?' },
+    /// ]);
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<WinePathViolation>> {
+        let mut violations = vec![];
+        for component in self.components() {
+            let name = match component {
+                WineComponent::Normal(name) => name,
+                _ => continue,
+            };
+            if let Some(character) = name
+                .chars()
+                .find(|c| c.is_ascii_control() || ILLEGAL_CHARACTERS.contains(c))
+            {
+                violations.push(WinePathViolation::IllegalCharacter {
+                    component: name.to_string(),
+                    character,
+                });
+            }
+            if is_reserved_name(name) {
+                violations.push(WinePathViolation::ReservedName {
+                    component: name.to_string(),
+                });
+            }
+            if name.ends_with(['.', ' ']) {
+                violations.push(WinePathViolation::TrailingDotOrSpace {
+                    component: name.to_string(),
+                });
+            }
+        }
+
+        if self.exceeds_max_path() {
+            violations.push(WinePathViolation::TooLong {
+                length: self.effective_len(),
+            });
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Get the length of this path as Windows counts it: in UTF-16 code units, not including the
+    /// `\\?\` prefix.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// assert_eq!(WinePath::from(r"c:\windows").effective_len(), 10);
+    /// assert_eq!(WinePath::from(r"\\?\c:\windows").effective_len(), 10);
+    /// ```
+    pub fn effective_len(&self) -> usize {
+        let path = self.0.strip_prefix(EXTENDED_PREFIX).unwrap_or(&self.0);
+        path.encode_utf16().count()
+    }
+
+    /// Check if this path is too long for most Windows APIs, i.e. it is at least `MAX_PATH`
+    /// (260) characters long and does not have the `\\?\` prefix.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// let path = WinePath::from(format!(r"c:\{}", "a".repeat(300)));
+    /// assert!(path.exceeds_max_path());
+    /// assert!(!path.to_extended_length().exceeds_max_path());
+    /// ```
+    pub fn exceeds_max_path(&self) -> bool {
+        !self.0.starts_with(EXTENDED_PREFIX) && self.effective_len() >= MAX_PATH
+    }
+
+    /// Lexically normalize this path: remove `.` and empty segments and resolve `..` segments,
+    /// without touching the filesystem.
+    ///
+    /// `/` separators are replaced by `\`. `..` segments can't go above the root of an absolute
+    /// path. `\\?\` and `\??\` paths are returned unchanged, as Windows does not normalize them
+    /// either.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// let path = WinePath::from(r"c:\foo\.\bar\..\baz");
+    /// assert_eq!(path.normalize().to_string(), r"c:\foo\baz");
+    /// let path = WinePath::from(r"c:\..\windows\\system32\");
+    /// assert_eq!(path.normalize().to_string(), r"c:\windows\system32");
+    /// let path = WinePath::from(r"..\foo\..\..\bar");
+    /// assert_eq!(path.normalize().to_string(), r"..\..\bar");
+    /// ```
+    pub fn normalize(&self) -> WinePath {
+        if is_verbatim(&self.0) {
+            return self.clone();
+        }
+        let path = self.0.replace('/', r"\");
+        let (root, rest, is_absolute) = split_root(&path);
+
+        let mut parts: Vec<&str> = vec![];
+        for part in rest.split('\\') {
+            match part {
+                "" | "." => {}
+                ".." => {
+                    if parts.last().is_some_and(|last| *last != "..") {
+                        parts.pop();
+                    } else if !is_absolute {
+                        parts.push("..");
+                    }
+                }
+                part => parts.push(part),
+            }
+        }
+
+        if root.is_empty() && parts.is_empty() {
+            return WinePath(".".to_string());
+        }
+        let mut normalized = root.to_string();
+        normalized.push_str(&parts.join(r"\"));
+        WinePath(normalized)
+    }
+
+    /// Get this path with its `..` segments resolved, to compare it. `.` segments are skipped by
+    /// [`WinePath::components`] already.
+    fn for_comparison(&self) -> Cow<'_, WinePath> {
+        if self.components().any(|c| c == WineComponent::ParentDir) {
+            Cow::Owned(self.normalize())
+        } else {
+            Cow::Borrowed(self)
+        }
+    }
+
+    /// Split this path into its root and the rest, without trailing separators.
+    fn split_trimmed(&self) -> (&str, &str) {
+        let (root, rest, _) = split_root(&self.0);
+        let rest = rest.trim_end_matches(|c| is_separator_in(&self.0, c));
+        (root, rest)
+    }
+
+    /// Get the path without its final component, or `None` if the path is a root or empty.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// let path = WinePath::from(r"c:\windows\system32\ddraw.dll");
+    /// assert_eq!(path.parent().unwrap().to_string(), r"c:\windows\system32");
+    /// let path = WinePath::from(r"c:\windows");
+    /// assert_eq!(path.parent().unwrap().to_string(), r"c:\");
+    /// assert!(WinePath::from(r"c:\").parent().is_none());
+    /// ```
+    pub fn parent(&self) -> Option<WinePath> {
+        let (root, rest) = self.split_trimmed();
+        if rest.is_empty() {
+            return None;
+        }
+        let is_separator = |c| is_separator_in(&self.0, c);
+        let parent = match rest.rfind(is_separator) {
+            Some(index) => rest[..index].trim_end_matches(is_separator),
+            None => "",
+        };
+        Some(WinePath(format!("{}{}", root, parent)))
+    }
+
+    /// Get the final component of the path, if it is a file or directory name.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// let path = WinePath::from(r"c:\windows\system32\ddraw.dll");
+    /// assert_eq!(path.file_name(), Some("ddraw.dll"));
+    /// assert_eq!(WinePath::from(r"c:\windows\").file_name(), Some("windows"));
+    /// assert_eq!(WinePath::from(r"c:\windows\..").file_name(), None);
+    /// assert_eq!(WinePath::from(r"c:\").file_name(), None);
+    /// ```
+    pub fn file_name(&self) -> Option<&str> {
+        let (_, rest) = self.split_trimmed();
+        let name = rest.rsplit(|c| is_separator_in(&self.0, c)).next()?;
+        match name {
+            "" | "." | ".." => None,
+            name => Some(name),
+        }
+    }
+
+    /// Get the file name without its extension.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// assert_eq!(WinePath::from(r"c:\data\archive.tar.gz").file_stem(), Some("archive.tar"));
+    /// assert_eq!(WinePath::from(r"c:\data\.hidden").file_stem(), Some(".hidden"));
+    /// ```
+    pub fn file_stem(&self) -> Option<&str> {
+        self.file_name().map(|name| split_file_name(name).0)
+    }
+
+    /// Get the extension of the file name, if any.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// assert_eq!(WinePath::from(r"c:\windows\notepad.exe").extension(), Some("exe"));
+    /// assert_eq!(WinePath::from(r"c:\windows\system32").extension(), None);
+    /// ```
+    pub fn extension(&self) -> Option<&str> {
+        self.file_name().and_then(|name| split_file_name(name).1)
+    }
+
+    /// Get a copy of this path with the extension replaced. An empty `extension` removes it.
+    ///
+    /// If the path has no file name, it is returned unchanged.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// let path = WinePath::from(r"c:\games\setup.exe");
+    /// assert_eq!(path.with_extension("ini").to_string(), r"c:\games\setup.ini");
+    /// assert_eq!(path.with_extension("").to_string(), r"c:\games\setup");
+    /// ```
+    pub fn with_extension(&self, extension: impl AsRef<str>) -> WinePath {
+        let extension = extension.as_ref();
+        let (root, rest) = self.split_trimmed();
+        let stem = match self.file_stem() {
+            Some(stem) => stem,
+            None => return self.clone(),
+        };
+        let name = self.file_name().unwrap_or_default();
+        let stem_end = root.len() + rest.len() - name.len() + stem.len();
+
+        let mut path = self.0[..stem_end].to_string();
+        if !extension.is_empty() {
+            path.push('.');
+            path.push_str(extension);
+        }
+        WinePath(path)
+    }
+
+    /// Extend this path with `path`.
+    ///
+    /// If `path` is absolute, it replaces this path. If `path` only has a root, like `\windows`,
+    /// it is placed on this path's drive.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// let mut path = WinePath::from(r"c:\windows");
+    /// path.push("system32");
+    /// assert_eq!(path.to_string(), r"c:\windows\system32");
+    /// path.push(r"\users");
+    /// assert_eq!(path.to_string(), r"c:\users");
+    /// path.push(r"d:\games");
+    /// assert_eq!(path.to_string(), r"d:\games");
+    /// ```
+    pub fn push(&mut self, path: impl AsRef<str>) {
+        let path = path.as_ref();
+        let (root, _, _) = split_root(path);
+        let (own_root, _, _) = split_root(&self.0);
+        let is_separator = |c| is_separator_in(&self.0, c);
+
+        if root.is_empty() {
+            let is_drive_relative_root = own_root == self.0 && self.0.ends_with(':');
+            if !self.0.is_empty() && !self.0.ends_with(is_separator) && !is_drive_relative_root {
+                self.0.push('\\');
+            }
+            self.0.push_str(path);
+        } else if root.len() == 1 {
+            let drive = own_root.trim_end_matches(is_separator).to_string();
+            self.0 = drive + path;
+        } else {
+            self.0 = path.to_string();
+        }
+    }
+
+    /// Truncate this path to its [parent][WinePath::parent]. Returns `false` if there is no
+    /// parent.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// let mut path = WinePath::from(r"c:\windows\system32");
+    /// assert!(path.pop());
+    /// assert_eq!(path.to_string(), r"c:\windows");
+    /// assert!(path.pop());
+    /// assert!(!path.pop());
+    /// assert_eq!(path.to_string(), r"c:\");
+    /// ```
+    pub fn pop(&mut self) -> bool {
+        match self.parent() {
+            Some(parent) => {
+                *self = parent;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Create a new path with `path` appended to this one. See [`WinePath::push`].
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// let path = WinePath::from(r"c:\Program Files").join(r"CoolApp\start.exe");
+    /// assert_eq!(path.to_string(), r"c:\Program Files\CoolApp\start.exe");
+    /// ```
+    pub fn join(&self, path: impl AsRef<str>) -> WinePath {
+        let mut joined = self.clone();
+        joined.push(path);
+        joined
+    }
+
+    /// Iterate over the components of this path.
+    ///
+    /// Like [`std::path::Path::components`], empty segments and `.` segments are skipped, except for a
+    /// `.` at the start of a relative path.
+    ///
+    /// ```rust
+    /// use winepath::{WineComponent, WinePath, WinePathPrefix};
+    /// let path = WinePath::from(r"C:\windows\.\system32\..");
+    /// let components: Vec<_> = path.components().collect();
+    /// assert_eq!(components, vec![
+    ///     WineComponent::Prefix(WinePathPrefix::Drive('C')),
+    ///     WineComponent::RootDir,
+    ///     WineComponent::Normal("windows"),
+    ///     WineComponent::Normal("system32"),
+    ///     WineComponent::ParentDir,
+    /// ]);
+    /// let path = WinePath::from(r"\\server\share\file.txt");
+    /// let components: Vec<_> = path.components().collect();
+    /// assert_eq!(components, vec![
+    ///     WineComponent::Prefix(WinePathPrefix::Unc("server", "share")),
+    ///     WineComponent::RootDir,
+    ///     WineComponent::Normal("file.txt"),
+    /// ]);
+    /// ```
+    pub fn components(&self) -> impl Iterator<Item = WineComponent<'_>> {
+        components(&self.0)
+    }
+
+    /// Check if this path is equal to `other`, ignoring case and separator style, like
+    /// Windows does.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// let path = WinePath::from(r"C:\Windows\System32");
+    /// assert!(path.eq_ignore_case(r"c:/windows/system32/"));
+    /// assert!(!path.eq_ignore_case(r"c:\windows"));
+    /// ```
+    pub fn eq_ignore_case(&self, other: impl AsRef<str>) -> bool {
+        let mut other = components(other.as_ref());
+        let mut own = self.components();
+        loop {
+            match (own.next(), other.next()) {
+                (None, None) => return true,
+                (Some(a), Some(b)) if component_eq_ignore_case(a, b) => {}
+                _ => return false,
+            }
+        }
+    }
+
+    /// Check if `base` is a prefix of this path, comparing whole components and ignoring case.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// let path = WinePath::from(r"C:\Program Files\CoolApp\start.exe");
+    /// assert!(path.starts_with(r"c:\program files"));
+    /// assert!(!path.starts_with(r"c:\program"));
+    /// ```
+    pub fn starts_with(&self, base: impl AsRef<str>) -> bool {
+        let mut own = self.components();
+        components(base.as_ref())
+            .all(|b| own.next().is_some_and(|a| component_eq_ignore_case(a, b)))
+    }
+
+    /// Check if `child` is a suffix of this path, comparing whole components and ignoring case.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// let path = WinePath::from(r"C:\Program Files\CoolApp\start.exe");
+    /// assert!(path.ends_with(r"coolapp\START.EXE"));
+    /// assert!(!path.ends_with(r"app\start.exe"));
+    /// ```
+    pub fn ends_with(&self, child: impl AsRef<str>) -> bool {
+        let own: Vec<_> = self.components().collect();
+        let child: Vec<_> = components(child.as_ref()).collect();
+        child.len() <= own.len()
+            && own[own.len() - child.len()..]
+                .iter()
+                .zip(&child)
+                .all(|(a, b)| component_eq_ignore_case(*a, *b))
+    }
+
+    /// Get this path with the `\\?\` extended-length prefix, which lifts the `MAX_PATH` limit in
+    /// Windows APIs.
+    ///
+    /// UNC paths become `\\?\UNC\server\share`. Paths that already have the prefix, `\\.\` device
+    /// paths and relative paths are returned unchanged.
+    ///
+    /// ```rust
+    /// use winepath::WinePath;
+    /// let path = WinePath::from(r"c:\windows\system32");
+    /// assert_eq!(path.to_extended_length().to_string(), r"\\?\c:\windows\system32");
+    /// let path = WinePath::from(r"\\server\share\file.txt");
+    /// assert_eq!(path.to_extended_length().to_string(), r"\\?\UNC\server\share\file.txt");
+    /// let path = WinePath::from(r"\\.\C:\foo");
+    /// assert_eq!(path.to_extended_length().to_string(), r"\\.\C:\foo");
+    /// ```
+    pub fn to_extended_length(&self) -> WinePath {
+        let path = self.0.as_str();
+        if path.starts_with(EXTENDED_PREFIX) || path.starts_with(DEVICE_PREFIX) {
+            self.clone()
+        } else if let Some(unc_path) = path.strip_prefix(r"\\") {
+            WinePath(format!(r"{}UNC\{}", EXTENDED_PREFIX, unc_path))
+        } else if is_drive_absolute(path) {
+            WinePath(format!("{}{}", EXTENDED_PREFIX, path))
+        } else {
+            self.clone()
+        }
+    }
+}
+/// Parse and validate a wine path.
+///
+/// Unlike `From<&str>`, this checks that the string is shaped like a Windows path.
+///
+/// ```rust
+/// use winepath::{ParseWinePathError, WinePath};
+/// let path: WinePath = r"C:\windows\system32".parse().unwrap();
+/// assert_eq!(path.to_string(), r"C:\windows\system32");
+/// assert!(r"relative\path.txt".parse::<WinePath>().is_ok());
+/// assert_eq!("".parse::<WinePath>().unwrap_err(), ParseWinePathError::Empty);
+/// assert_eq!(r"1:\foo".parse::<WinePath>().unwrap_err(), ParseWinePathError::MisplacedColon);
+/// assert_eq!(r"\\server".parse::<WinePath>().unwrap_err(), ParseWinePathError::IncompleteUnc);
+/// ```
+impl FromStr for WinePath {
+    type Err = ParseWinePathError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        if path.is_empty() {
+            return Err(ParseWinePathError::Empty);
+        }
+        if path.contains('\0') {
+            return Err(ParseWinePathError::ContainsNul);
+        }
+        let (root, rest, _) = split_root(path);
+        match parse_prefix(root) {
+            Some(WinePathPrefix::Unc(server, share))
+            | Some(WinePathPrefix::VerbatimUnc(server, share))
+                if server.is_empty() || share.is_empty() =>
+            {
+                return Err(ParseWinePathError::IncompleteUnc);
+            }
+            _ => {}
+        }
+        if rest.contains(':') {
+            return Err(ParseWinePathError::MisplacedColon);
+        }
+        Ok(WinePath(path.to_string()))
+    }
+}
+
+/// Wine paths are compared like Windows does: component by component, ignoring case and
+/// separator style, after resolving `.` and `..` segments like [`WinePath::normalize`].
+///
+/// ```rust
+/// use winepath::WinePath;
+/// use std::collections::HashSet;
+/// assert_eq!(WinePath::from(r"c:\Foo"), WinePath::from(r"C:/foo/"));
+/// assert_eq!(WinePath::from(r"c:\a\..\b"), WinePath::from(r"C:\B"));
+/// let mut set = HashSet::new();
+/// set.insert(WinePath::from(r"c:\Foo"));
+/// assert!(!set.insert(WinePath::from(r"C:\foo")));
+/// ```
+impl PartialEq for WinePath {
+    fn eq(&self, other: &Self) -> bool {
+        self.for_comparison()
+            .eq_ignore_case(&other.for_comparison().0)
+    }
+}
+impl Eq for WinePath {}
+impl PartialOrd for WinePath {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for WinePath {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (own, other) = (self.for_comparison(), other.for_comparison());
+        let mut own = own.components();
+        let mut other = other.components();
+        loop {
+            match (own.next(), other.next()) {
+                (None, None) => return Ordering::Equal,
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(a), Some(b)) => match component_cmp_ignore_case(a, b) {
+                    Ordering::Equal => {}
+                    ordering => return ordering,
+                },
+            }
+        }
+    }
+}
+impl Hash for WinePath {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for component in self.for_comparison().components() {
+            hash_component(component, state);
+        }
+    }
+}
+/// Display the path as is.
+///
+/// The alternate form, `{:#}`, displays the path with forward slashes instead, which most Windows
+/// programs accept too. Paths with the `\\?\` or `\??\` prefix are always displayed as is.
+///
+/// ```rust
+/// use winepath::WinePath;
+/// let path = WinePath::from(r"c:\Program Files\App");
+/// assert_eq!(format!("{}", path), r"c:\Program Files\App");
+/// assert_eq!(format!("{:#}", path), "c:/Program Files/App");
+/// ```
+impl Display for WinePath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if f.alternate() && !is_verbatim(&self.0) {
+            write!(f, "{}", self.0.replace('\\', "/"))
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}